@@ -0,0 +1,262 @@
+use crate::model::Model;
+
+pub const BINS: usize = 10;
+
+const EPSILON: f64 = 1e-12;
+const FIT_ITERATIONS: usize = 100;
+const TOLERANCE: f64 = 1e-9;
+const RIDGE: f64 = 1e-6;
+// Beyond these, every output is squashed to 0.5 or pushed to 0 and 1
+const MIN_TEMPERATURE: f64 = 0.01;
+const MAX_TEMPERATURE: f64 = 100.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReliabilityBin {
+    pub count: usize,
+    pub mean_predicted: f64,
+    pub observed: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Calibrator {
+    None,
+    // p' = sigmoid(a * logit(p) + b)
+    Platt { a: f64, b: f64 },
+    // p' = sigmoid(logit(p) / t)
+    Temperature { t: f64 },
+}
+
+fn logit(p: f64) -> f64 {
+    let p = p.clamp(EPSILON, 1.0 - EPSILON);
+    (p / (1.0 - p)).ln()
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+// Returns the (predicted probability, target) of each sample, given in the units of the data.
+// The model is a copy of the neuron, so the neuron being trained and drawn isn't touched.
+pub fn predictions(model: &Model, data: &[(Vec<f64>, f64)]) -> Vec<(f64, f64)> {
+    data.iter()
+        .map(|(inputs, target)| (model.predict(inputs), *target))
+        .collect()
+}
+
+// Group the predictions in equal width bins, comparing the mean predicted probability with the observed frequency
+pub fn reliability_bins(predictions: &[(f64, f64)], bins: usize) -> Vec<ReliabilityBin> {
+    let mut result = vec![
+        ReliabilityBin {
+            count: 0,
+            mean_predicted: 0.0,
+            observed: 0.0,
+        };
+        bins
+    ];
+
+    for (predicted, target) in predictions {
+        let index = ((predicted * bins as f64) as usize).min(bins - 1);
+        result[index].count += 1;
+        result[index].mean_predicted += predicted;
+        result[index].observed += target;
+    }
+
+    for bin in result.iter_mut().filter(|b| b.count > 0) {
+        bin.mean_predicted /= bin.count as f64;
+        bin.observed /= bin.count as f64;
+    }
+
+    result
+}
+
+pub fn brier_score(predictions: &[(f64, f64)]) -> f64 {
+    predictions
+        .iter()
+        .map(|(predicted, target)| (predicted - target).powi(2))
+        .sum::<f64>()
+        / predictions.len() as f64
+}
+
+pub fn expected_calibration_error(bins: &[ReliabilityBin]) -> f64 {
+    let total: usize = bins.iter().map(|b| b.count).sum();
+
+    bins.iter()
+        .map(|b| b.count as f64 / total as f64 * (b.mean_predicted - b.observed).abs())
+        .sum()
+}
+
+impl Calibrator {
+    pub fn apply(&self, p: f64) -> f64 {
+        match self {
+            Calibrator::None => p,
+            Calibrator::Platt { a, b } => sigmoid(a * logit(p) + b),
+            Calibrator::Temperature { t } => sigmoid(logit(p) / t),
+        }
+    }
+
+    // Platt scaling fits a and b with smoothed targets (as in Platt's paper) to avoid overfitting small sets
+    pub fn fit_platt(predictions: &[(f64, f64)]) -> Calibrator {
+        let positives = predictions.iter().filter(|(_, t)| *t >= 0.5).count() as f64;
+        let negatives = predictions.len() as f64 - positives;
+
+        let high = (positives + 1.0) / (positives + 2.0);
+        let low = 1.0 / (negatives + 2.0);
+
+        let samples: Vec<(f64, f64)> = predictions
+            .iter()
+            .map(|(p, t)| (logit(*p), if *t >= 0.5 { high } else { low }))
+            .collect();
+
+        let (a, b) = fit_logistic(&samples, true);
+
+        Calibrator::Platt { a, b }
+    }
+
+    // Temperature scaling is Platt with a = 1 / t and b = 0, so the ranking of the predictions never changes.
+    // When the best a isn't positive, the outputs are ranked the wrong way round and no temperature helps.
+    pub fn fit_temperature(predictions: &[(f64, f64)]) -> Calibrator {
+        let samples: Vec<(f64, f64)> = predictions.iter().map(|(p, t)| (logit(*p), *t)).collect();
+
+        let (a, _) = fit_logistic(&samples, false);
+
+        if a <= 0.0 {
+            return Calibrator::None;
+        }
+
+        Calibrator::Temperature {
+            t: (1.0 / a).clamp(MIN_TEMPERATURE, MAX_TEMPERATURE),
+        }
+    }
+}
+
+fn log_loss(samples: &[(f64, f64)], a: f64, b: f64) -> f64 {
+    samples
+        .iter()
+        .map(|(x, t)| {
+            let p = sigmoid(a * x + b).clamp(EPSILON, 1.0 - EPSILON);
+            -(t * p.ln() + (1.0 - t) * (1.0 - p).ln())
+        })
+        .sum()
+}
+
+// Newton's method on the log loss of sigmoid(a * x + b) over the (logit, target) samples.
+// The step is halved while it doesn't improve the loss, since the logits can be large.
+fn fit_logistic(samples: &[(f64, f64)], fit_bias: bool) -> (f64, f64) {
+    let mut a = 1.0;
+    let mut b = 0.0;
+
+    for _ in 0..FIT_ITERATIONS {
+        let (mut g_a, mut g_b, mut h_aa, mut h_ab, mut h_bb) = (0.0, 0.0, 0.0, 0.0, 0.0);
+
+        for (x, t) in samples {
+            let p = sigmoid(a * x + b);
+            let w = p * (1.0 - p);
+
+            g_a += (p - t) * x;
+            g_b += p - t;
+            h_aa += w * x * x;
+            h_ab += w * x;
+            h_bb += w;
+        }
+
+        h_aa += RIDGE;
+        h_bb += RIDGE;
+
+        let (step_a, step_b) = if fit_bias {
+            let det = h_aa * h_bb - h_ab * h_ab;
+            (
+                (h_bb * g_a - h_ab * g_b) / det,
+                (h_aa * g_b - h_ab * g_a) / det,
+            )
+        } else {
+            (g_a / h_aa, 0.0)
+        };
+
+        let loss = log_loss(samples, a, b);
+        let mut scale = 1.0;
+
+        while scale > EPSILON && log_loss(samples, a - scale * step_a, b - scale * step_b) > loss {
+            scale /= 2.0;
+        }
+
+        a -= scale * step_a;
+        b -= scale * step_b;
+
+        if (scale * step_a).abs() < TOLERANCE && (scale * step_b).abs() < TOLERANCE {
+            break;
+        }
+    }
+
+    (a, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 20 samples for each true probability 0.05, 0.15, ..., 0.95, with as many positives as the
+    // probability gives, predicted through the distortion
+    fn samples(distort: impl Fn(f64) -> f64) -> Vec<(f64, f64)> {
+        (0..BINS)
+            .flat_map(|bin| {
+                let q = (bin as f64 + 0.5) / BINS as f64;
+                let positives = (q * 20.0).round() as usize;
+                let p = distort(q);
+
+                (0..20).map(move |i| (p, if i < positives { 1.0 } else { 0.0 }))
+            })
+            .collect()
+    }
+
+    fn mean_log_loss(predictions: &[(f64, f64)], calibrator: Calibrator) -> f64 {
+        let samples: Vec<(f64, f64)> = predictions
+            .iter()
+            .map(|(p, t)| (logit(calibrator.apply(*p)), *t))
+            .collect();
+
+        log_loss(&samples, 1.0, 0.0) / samples.len() as f64
+    }
+
+    #[test]
+    fn calibrated_bins_have_no_error() {
+        let bins = reliability_bins(&samples(|q| q), BINS);
+
+        assert!(bins.iter().all(|bin| bin.count == 20));
+        assert!(expected_calibration_error(&bins) < 1e-12);
+    }
+
+    #[test]
+    fn calibration_lowers_log_loss() {
+        // Overconfident and underconfident outputs
+        for factor in [3.0, 1.0 / 3.0] {
+            let predictions = samples(|q| sigmoid(factor * logit(q)));
+            let before = mean_log_loss(&predictions, Calibrator::None);
+
+            for calibrator in [
+                Calibrator::fit_platt(&predictions),
+                Calibrator::fit_temperature(&predictions),
+            ] {
+                assert!(mean_log_loss(&predictions, calibrator) < before - 1e-3);
+            }
+
+            let Calibrator::Temperature { t } = Calibrator::fit_temperature(&predictions) else {
+                panic!("No temperature fitted");
+            };
+            assert!((t - factor).abs() < 0.05 * factor, "t = {}", t);
+        }
+    }
+
+    #[test]
+    fn temperature_stays_bounded() {
+        // Perfectly separated outputs push the temperature to 0
+        let separated = [(0.6, 1.0), (0.55, 1.0), (0.45, 0.0), (0.4, 0.0)];
+        let Calibrator::Temperature { t } = Calibrator::fit_temperature(&separated) else {
+            panic!("No temperature fitted");
+        };
+        assert!((MIN_TEMPERATURE..0.1).contains(&t), "t = {}", t);
+
+        // Ranked the wrong way round
+        let reversed = [(0.9, 0.0), (0.8, 0.0), (0.2, 1.0), (0.1, 1.0)];
+        assert_eq!(Calibrator::fit_temperature(&reversed), Calibrator::None);
+    }
+}
@@ -0,0 +1,531 @@
+use crate::{
+    cross_validation::{self, CrossValidationReport},
+    dataset::{Dataset, DatasetConfig},
+    formats,
+    model::{Model, MODEL_PATH},
+    preprocessing::Scaling,
+    split::{self, SplitConfig, Subset},
+};
+
+pub const DEFAULT_OUTPUT: &str = "./predictions.csv";
+
+pub const USAGE: &str = "Usage:
+    iris-neuron-demo [options] [dataset options] [split options]        Open the demo window
+    iris-neuron-demo cv [options] [dataset options] [split options]     Run k-fold cross-validation on the train and validation sets
+    iris-neuron-demo export [options] [dataset options] [split options] Write the predictions of a saved model for every row
+
+Options for the window:
+    --output <path>          File the dataset panel exports to, by its extension (default ./predictions.csv)
+
+Options for cv:
+    --folds <k>              Number of folds (default 5)
+    --stratified             Keep the class proportions in every fold
+    --features <type>        sepal, petal or all (default sepal)
+    --positive <class>       Class with the target 1 (default the last class, --iris works too)
+    --negative <class|rest>  Class with the target 0, or rest for every other class (default the first class)
+    --epochs <n>             Training epochs for each fold (default 100)
+    --learning-rate <rate>   Learning rate of the neuron (default 0.5)
+    --scaling <scaling>      none, standard, minmax or robust, fitted on the training folds (default standard)
+
+Options for export:
+    --model <path>           Model saved from the window (default ./model.txt)
+    --output <path>          File to write, as CSV, ARFF, JSON or JSON lines by its extension (default ./predictions.csv)
+    --rows <rows>            all, train, validation or test (default all)
+    --positive <class>       Class with the target 1 (default the one of the model)
+    --negative <class|rest>  Class with the target 0, or rest (default the one of the model)
+                             The split options replace the split saved with the model
+
+Dataset options:
+    --dataset <path>             Dataset file to load (default $IRIS_DATASET or ./iris.csv, then the copy built into the binary)
+    --feature-columns <a,b,...>  Feature columns by name or index (default every column but the label, Id and export outputs)
+    --label-column <column>      Label column by name or index (default the last column that isn't an export output)
+    --label-map <raw=name,...>   Rename labels to class names
+    --header <auto|yes|no>       Whether the first row is a header (default auto)
+    --tolerant                   Skip the rows that can't be read instead of failing
+
+Split options:
+    --train-ratio <ratio>        Fraction of the samples used for training (default 0.6)
+    --validation-ratio <ratio>   Fraction used for validation, the rest is the test set (default 0.2)
+    --seed <n>                   Seed of the shuffle (default 42)
+    --unstratified-split         Don't keep the class proportions in every set";
+
+pub struct WindowOptions {
+    pub dataset: DatasetConfig,
+    pub split: SplitConfig,
+    // Where the dataset panel exports the predictions
+    pub output: String,
+}
+
+pub enum Command {
+    Window(WindowOptions),
+    CrossValidation(CrossValidationOptions),
+    Export(ExportOptions),
+    Help,
+}
+
+pub struct CrossValidationOptions {
+    pub dataset: DatasetConfig,
+    pub split: SplitConfig,
+    pub folds: usize,
+    pub stratified: bool,
+    pub selected: i32,
+    pub positive: Option<String>,
+    pub negative: Option<String>,
+    pub epochs: usize,
+    pub learning_rate: f64,
+    pub scaling: Scaling,
+}
+
+pub struct ExportOptions {
+    pub dataset: DatasetConfig,
+    // None for the split of the model
+    pub split: Option<SplitConfig>,
+    pub model: String,
+    pub output: String,
+    // None for every row
+    pub rows: Option<Subset>,
+    pub positive: Option<String>,
+    pub negative: Option<String>,
+}
+
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    match args.first().map(|a| a.as_str()) {
+        Some("cv") => parse_cross_validation(&args[1..]),
+        Some("export") => parse_export(&args[1..]),
+        Some("help" | "--help" | "-h") => Ok(Command::Help),
+        _ => {
+            let mut options = WindowOptions {
+                dataset: DatasetConfig::default(),
+                split: SplitConfig::default(),
+                output: DEFAULT_OUTPUT.to_owned(),
+            };
+            let mut args = args.iter();
+
+            while let Some(arg) = args.next() {
+                if arg == "--output" {
+                    options.output = next_value(&mut args, arg)?.clone();
+                } else if !parse_dataset_option(arg, &mut args, &mut options.dataset)?
+                    && !parse_split_option(arg, &mut args, &mut options.split)?
+                {
+                    return Err(format!("Unknown option '{}'", arg));
+                }
+            }
+
+            validate_split(&options.split)?;
+
+            Ok(Command::Window(options))
+        }
+    }
+}
+
+pub fn run(command: Command) {
+    let result = match command {
+        Command::CrossValidation(options) => cross_validation_command(options),
+        Command::Export(options) => export_command(options),
+        Command::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Command::Window(_) => Ok(()),
+    };
+
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+}
+
+fn next_value<'a>(
+    args: &mut impl Iterator<Item = &'a String>,
+    flag: &str,
+) -> Result<&'a String, String> {
+    args.next()
+        .ok_or_else(|| format!("Missing value for {}", flag))
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, flag: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for {}", value, flag))
+}
+
+// Returns false when the argument isn't a dataset option
+fn parse_dataset_option<'a>(
+    arg: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+    config: &mut DatasetConfig,
+) -> Result<bool, String> {
+    match arg {
+        "--dataset" => {
+            config.path = next_value(args, arg)?.clone();
+            config.path_given = true;
+        }
+        "--feature-columns" => {
+            config.feature_columns = next_value(args, arg)?
+                .split(',')
+                .map(|c| c.trim().to_owned())
+                .collect()
+        }
+        "--label-column" => config.label_column = Some(next_value(args, arg)?.clone()),
+        "--label-map" => {
+            for pair in next_value(args, arg)?.split(',') {
+                let (raw, name) = pair
+                    .split_once('=')
+                    .ok_or_else(|| format!("Invalid label mapping '{}'", pair))?;
+
+                // User mappings take precedence over the default ones
+                config
+                    .label_map
+                    .insert(0, (raw.trim().to_owned(), name.trim().to_owned()));
+            }
+        }
+        "--tolerant" => config.tolerant = true,
+        "--header" => {
+            config.has_header = match next_value(args, arg)?.as_str() {
+                "auto" => None,
+                "yes" => Some(true),
+                "no" => Some(false),
+                value => return Err(format!("Invalid value '{}' for {}", value, arg)),
+            }
+        }
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+// Returns false when the argument isn't a split option
+fn parse_split_option<'a>(
+    arg: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+    config: &mut SplitConfig,
+) -> Result<bool, String> {
+    match arg {
+        "--train-ratio" => config.train_ratio = parse_number(next_value(args, arg)?, arg)?,
+        "--validation-ratio" => {
+            config.validation_ratio = parse_number(next_value(args, arg)?, arg)?
+        }
+        "--seed" => config.seed = parse_number(next_value(args, arg)?, arg)?,
+        "--unstratified-split" => config.stratified = false,
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+fn validate_split(config: &SplitConfig) -> Result<(), String> {
+    if config.train_ratio <= 0.0
+        || config.validation_ratio < 0.0
+        || config.test_ratio() < -f64::EPSILON
+    {
+        return Err(
+            "The train ratio must be positive and the train and validation ratios can't add up to more than 1"
+                .to_owned(),
+        );
+    }
+
+    Ok(())
+}
+
+fn parse_cross_validation(args: &[String]) -> Result<Command, String> {
+    let mut options = CrossValidationOptions {
+        dataset: DatasetConfig::default(),
+        split: SplitConfig::default(),
+        folds: cross_validation::DEFAULT_FOLDS,
+        stratified: false,
+        selected: 0,
+        positive: None,
+        negative: None,
+        epochs: cross_validation::DEFAULT_EPOCHS,
+        learning_rate: cross_validation::DEFAULT_LEARNING_RATE,
+        scaling: Scaling::Standard,
+    };
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--folds" => options.folds = parse_number(next_value(&mut args, arg)?, arg)?,
+            "--stratified" => options.stratified = true,
+            "--features" => {
+                options.selected = match next_value(&mut args, arg)?.as_str() {
+                    "sepal" => 0,
+                    "petal" => 1,
+                    "all" => 2,
+                    value => return Err(format!("Invalid value '{}' for {}", value, arg)),
+                }
+            }
+            "--positive" | "--iris" => options.positive = Some(next_value(&mut args, arg)?.clone()),
+            "--negative" => options.negative = Some(next_value(&mut args, arg)?.clone()),
+            "--epochs" => options.epochs = parse_number(next_value(&mut args, arg)?, arg)?,
+            "--learning-rate" => {
+                options.learning_rate = parse_number(next_value(&mut args, arg)?, arg)?
+            }
+            "--scaling" => {
+                let value = next_value(&mut args, arg)?;
+                options.scaling = Scaling::from_name(value)
+                    .ok_or_else(|| format!("Invalid value '{}' for {}", value, arg))?
+            }
+            _ => {
+                if !parse_dataset_option(arg, &mut args, &mut options.dataset)?
+                    && !parse_split_option(arg, &mut args, &mut options.split)?
+                {
+                    return Err(format!("Unknown option '{}'", arg));
+                }
+            }
+        }
+    }
+
+    validate_split(&options.split)?;
+
+    Ok(Command::CrossValidation(options))
+}
+
+// The positive and negative classes given by name. An exact class name comes first, then class
+// names like "Iris Virginica" can be given as "virginica".
+fn class_pair(
+    csv_data: &Dataset,
+    positive: Option<&str>,
+    negative: Option<&str>,
+) -> Result<(usize, Option<usize>), String> {
+    let find_class = |name: &str, flag: &str| {
+        csv_data
+            .class_names
+            .iter()
+            .position(|class| class == name)
+            .or_else(|| {
+                csv_data
+                    .class_names
+                    .iter()
+                    .position(|class| class.to_lowercase().contains(&name.to_lowercase()))
+            })
+            .ok_or_else(|| format!("Invalid class '{}' for {}", name, flag))
+    };
+
+    let iris_type = match positive {
+        Some(name) => find_class(name, "--positive")?,
+        None => csv_data.class_names.len() - 1,
+    };
+
+    let negative = match negative {
+        Some("rest") => None,
+        Some(name) => Some(find_class(name, "--negative")?),
+        None => Some(0),
+    };
+
+    if negative == Some(iris_type) {
+        return Err("The positive and negative classes must be different".to_owned());
+    }
+
+    Ok((iris_type, negative))
+}
+
+fn cross_validation_command(options: CrossValidationOptions) -> Result<(), String> {
+    let csv_data =
+        Dataset::load_with_fallback(&options.dataset).map_err(|error| error.to_string())?;
+
+    if csv_data.source != options.dataset.path {
+        eprintln!("Using {}", csv_data.source);
+    }
+
+    for skipped in csv_data.skipped.iter() {
+        eprintln!("Skipped {}", skipped);
+    }
+
+    let (iris_type, negative) = class_pair(
+        &csv_data,
+        options.positive.as_deref(),
+        options.negative.as_deref(),
+    )?;
+
+    let data = csv_data.get_data(iris_type, negative);
+
+    // The test set stays out of the cross-validation
+    let split = split::split(&data, &options.split);
+    let mut indexes = split.train;
+    indexes.extend(split.validation);
+
+    let data = Dataset::select(&data, &indexes);
+
+    if options.folds < 2 || options.folds > data.len() {
+        return Err(format!("--folds must be between 2 and {}", data.len()));
+    }
+
+    let features = csv_data.selected_features(options.selected);
+    let train_data = Dataset::get_train_data(&data, &features, iris_type);
+
+    let report = cross_validation::cross_validate(
+        &train_data,
+        options.folds,
+        options.stratified,
+        options.scaling,
+        options.epochs,
+        options.learning_rate,
+        options.split.seed,
+    )?;
+
+    let feature_names: Vec<&str> = features
+        .iter()
+        .map(|&i| csv_data.feature_names[i].as_str())
+        .collect();
+
+    println!(
+        "{} ({}, {} samples, {} scaling)",
+        csv_data.pair_name(iris_type, negative),
+        feature_names.join(", "),
+        train_data.len(),
+        options.scaling.name()
+    );
+    print_report(&report);
+
+    Ok(())
+}
+
+fn parse_export(args: &[String]) -> Result<Command, String> {
+    let mut options = ExportOptions {
+        dataset: DatasetConfig::default(),
+        split: None,
+        model: MODEL_PATH.to_owned(),
+        output: DEFAULT_OUTPUT.to_owned(),
+        rows: None,
+        positive: None,
+        negative: None,
+    };
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--model" => options.model = next_value(&mut args, arg)?.clone(),
+            "--output" => options.output = next_value(&mut args, arg)?.clone(),
+            "--rows" => {
+                options.rows = match next_value(&mut args, arg)?.as_str() {
+                    "all" => None,
+                    "train" => Some(Subset::Train),
+                    "validation" => Some(Subset::Validation),
+                    "test" => Some(Subset::Test),
+                    value => return Err(format!("Invalid value '{}' for {}", value, arg)),
+                }
+            }
+            "--positive" | "--iris" => options.positive = Some(next_value(&mut args, arg)?.clone()),
+            "--negative" => options.negative = Some(next_value(&mut args, arg)?.clone()),
+            _ => {
+                // The split options replace the split of the model
+                let mut split = options.split.clone().unwrap_or_default();
+
+                if parse_split_option(arg, &mut args, &mut split)? {
+                    options.split = Some(split);
+                } else if !parse_dataset_option(arg, &mut args, &mut options.dataset)? {
+                    return Err(format!("Unknown option '{}'", arg));
+                }
+            }
+        }
+    }
+
+    if let Some(split) = &options.split {
+        validate_split(split)?;
+    }
+
+    Ok(Command::Export(options))
+}
+
+fn export_command(options: ExportOptions) -> Result<(), String> {
+    let model = Model::load(&options.model)?;
+
+    let csv_data =
+        Dataset::load_with_fallback(&options.dataset).map_err(|error| error.to_string())?;
+
+    if csv_data.source != options.dataset.path {
+        eprintln!("Using {}", csv_data.source);
+    }
+
+    // The features of the model, by name
+    let features = model
+        .feature_names
+        .iter()
+        .map(|name| {
+            csv_data
+                .feature_names
+                .iter()
+                .position(|f| f == name)
+                .ok_or_else(|| format!("The dataset has no feature '{}' for the model", name))
+        })
+        .collect::<Result<Vec<usize>, String>>()?;
+
+    // The classes and the split the model was trained on, unless given
+    let (iris_type, negative) = class_pair(
+        &csv_data,
+        Some(options.positive.as_deref().unwrap_or(&model.positive)),
+        Some(
+            options
+                .negative
+                .as_deref()
+                .unwrap_or(model.negative.as_deref().unwrap_or("rest")),
+        ),
+    )?;
+
+    // The same split as the window, so the subsets match
+    let data = csv_data.get_data(iris_type, negative);
+    let split = split::split(&data, options.split.as_ref().unwrap_or(&model.split));
+
+    let rows = formats::prediction_rows(
+        &csv_data,
+        &data,
+        &split.subsets,
+        iris_type,
+        negative,
+        options.rows,
+        |sample| {
+            let inputs: Vec<f64> = features.iter().map(|&i| sample.features[i]).collect();
+            model.predict(&inputs)
+        },
+    );
+
+    formats::write_predictions(
+        &options.output,
+        &csv_data.pair_name(iris_type, negative),
+        &csv_data.feature_names,
+        &csv_data.class_names,
+        &rows,
+    )?;
+
+    println!("Wrote {} rows to {}", rows.len(), options.output);
+
+    Ok(())
+}
+
+fn print_report(report: &CrossValidationReport) {
+    println!(
+        "{}-fold{} cross-validation",
+        report.folds,
+        if report.stratified { " stratified" } else { "" }
+    );
+
+    println!();
+    println!(
+        "{:<6} {:>9} {:>9} {:>9} {:>9}",
+        "Fold", "Accuracy", "Precision", "Recall", "Log loss"
+    );
+
+    for (i, fold) in report.fold_metrics.iter().enumerate() {
+        println!(
+            "{:<6} {:>9.4} {:>9.4} {:>9.4} {:>9.4}",
+            i + 1,
+            fold.accuracy,
+            fold.precision,
+            fold.recall,
+            fold.log_loss
+        );
+    }
+
+    println!();
+
+    for (name, metric) in [
+        ("Accuracy", report.accuracy),
+        ("Precision", report.precision),
+        ("Recall", report.recall),
+        ("Log loss", report.log_loss),
+    ] {
+        println!("{:<10} {:.4} ± {:.4}", name, metric.mean, metric.std);
+    }
+}
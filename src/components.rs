@@ -1,6 +1,30 @@
+use std::ffi::CString;
+
 use raylib::prelude::*;
 
-use crate::{train_data::Iris, State};
+use crate::{
+    calibration::{self, Calibrator},
+    cross_validation,
+    dataset::{class_color, short_name, unique_short_names, Dataset},
+    error_screen::typed_path,
+    formats,
+    imbalance::{self, Resampling, Weighting},
+    model::{self, Model},
+    perturbation::Corruption,
+    preprocessing::SCALINGS,
+    projection::{Method, METHODS},
+    separation_graph::probability_color,
+    split::Subset,
+    state::{
+        add_sample, apply_model, brushed_samples, cross_validation_data, current_model,
+        fitting_data, load_dataset, move_sample, pair_data_type, prediction_rows, predictor,
+        remove_sample, sample_prediction, selected_features, slice_means, unscaled_train_data,
+        update_data_type, update_decision_line, update_projection, update_slice, ALL_FEATURES,
+    },
+    statistics,
+    synthetic::{self, GENERATORS},
+    State, WINDOW_HEIGHT, WINDOW_WIDTH,
+};
 
 pub fn iris_data_type_box(
     d: &mut RaylibDrawHandle,
@@ -18,7 +42,7 @@ pub fn iris_data_type_box(
 
     d.gui_dropdown_box(
         rect,
-        Some(rstr!("Sepal;Petal;All")),
+        Some(rstr!("Sepal;Petal;All;Custom")),
         &mut state.iris_select.1,
         state.iris_select.0,
     );
@@ -31,7 +55,258 @@ pub fn iris_data_type_box(
     }
 }
 
-pub fn iris_type_box(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) -> Option<Iris> {
+fn feature_box(
+    d: &mut RaylibDrawHandle,
+    rect: Rectangle,
+    names: &[String],
+    component: &mut (bool, i32),
+) -> bool {
+    let collision = rect.check_collision_point_rec(d.get_mouse_position());
+    let pressed = d.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
+
+    let last_result = component.1;
+
+    if collision && pressed {
+        component.0 = !component.0;
+    }
+
+    let options = CString::new(names.join(";")).unwrap();
+
+    d.gui_dropdown_box(
+        rect,
+        Some(options.as_c_str()),
+        &mut component.1,
+        component.0,
+    );
+
+    if last_result != component.1 {
+        component.0 = false;
+        true
+    } else {
+        false
+    }
+}
+
+// Any two features for the graph and the neuron. Returns the data type to switch to.
+pub fn feature_boxes(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) -> Option<i32> {
+    let names = state.csv_data.feature_names.clone();
+    let previous = (state.x_feature.1, state.y_feature.1);
+
+    d.draw_text("X axis", rect.x as i32, rect.y as i32, 15, Color::BLACK);
+    d.draw_text(
+        "Y axis",
+        rect.x as i32,
+        rect.y as i32 + 55,
+        15,
+        Color::BLACK,
+    );
+
+    // The Y box is drawn first, so the open X box covers it
+    let y_changed = feature_box(
+        d,
+        Rectangle::new(rect.x, rect.y + 75.0, rect.width, 25.0),
+        &names,
+        &mut state.y_feature,
+    );
+    let x_changed = feature_box(
+        d,
+        Rectangle::new(rect.x, rect.y + 20.0, rect.width, 25.0),
+        &names,
+        &mut state.x_feature,
+    );
+
+    if !x_changed && !y_changed {
+        return None;
+    }
+
+    // Picking the feature of the other axis swaps the two
+    if state.x_feature.1 == state.y_feature.1 {
+        if x_changed {
+            state.y_feature.1 = previous.0;
+        } else {
+            state.x_feature.1 = previous.1;
+        }
+    }
+
+    // A slice of all the features only changes its axes
+    if state.iris_select.1 == ALL_FEATURES
+        && METHODS[state.projection_select as usize] == Method::Slice
+    {
+        update_projection(state);
+        return None;
+    }
+
+    Some(pair_data_type(state))
+}
+
+// Probability heatmap behind the separation graph, with optional contours, and the size of the
+// points by their loss
+pub fn graph_option_boxes(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) {
+    let last = (state.show_heatmap, state.show_contours, state.loss_size);
+
+    d.gui_check_box(
+        Rectangle::new(rect.x, rect.y, 20.0, 20.0),
+        Some(rstr!("Heatmap")),
+        &mut state.show_heatmap,
+    );
+    d.gui_check_box(
+        Rectangle::new(rect.x + 75.0, rect.y, 20.0, 20.0),
+        Some(rstr!("Contours")),
+        &mut state.show_contours,
+    );
+    d.gui_check_box(
+        Rectangle::new(rect.x + 155.0, rect.y, 20.0, 20.0),
+        Some(rstr!("Loss size")),
+        &mut state.loss_size,
+    );
+
+    if last != (state.show_heatmap, state.show_contours, state.loss_size) {
+        update_decision_line(state);
+    }
+}
+
+// Zoom with the mouse wheel around the cursor and pan by dragging with the middle button, or the
+// left one while holding shift. "Fit" shows all the data again, a locked view can't be moved.
+pub fn graph_view_controls(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) {
+    let mut changed = false;
+
+    if !state.lock_axes
+        && d.gui_button(
+            Rectangle::new(rect.x, rect.y, 40.0, rect.height),
+            Some(rstr!("Fit")),
+        )
+    {
+        state.separation_graph.fit();
+        changed = true;
+    }
+
+    d.gui_check_box(
+        Rectangle::new(rect.x + 50.0, rect.y, rect.height, rect.height),
+        Some(rstr!("Lock axes")),
+        &mut state.lock_axes,
+    );
+
+    let mouse = d.get_mouse_position();
+    let shift =
+        d.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || d.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
+
+    if state.lock_axes {
+        state.panning = false;
+    } else if state.panning {
+        if d.is_mouse_button_released(MouseButton::MOUSE_BUTTON_MIDDLE)
+            || d.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT)
+        {
+            state.panning = false;
+        } else if d.get_mouse_delta() != Vector2::zero() {
+            state.separation_graph.pan(d.get_mouse_delta());
+            changed = true;
+        }
+    } else if state.separation_graph.contains(mouse) {
+        if d.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_MIDDLE)
+            || (shift && d.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT))
+        {
+            state.panning = true;
+        }
+
+        let wheel = d.get_mouse_wheel_move();
+        if wheel != 0.0 {
+            state.separation_graph.zoom(mouse, 0.9_f32.powf(wheel));
+            changed = true;
+        }
+    }
+
+    // The line and the heatmap are computed for the visible part
+    if changed {
+        update_decision_line(state);
+    }
+}
+
+// How the "All" features are shown in the separation graph. A slice fixes the features that
+// aren't on the axes with a slider each.
+pub fn projection_box(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) {
+    if state.projection.is_none() {
+        return;
+    }
+
+    d.draw_text("Projection", rect.x as i32, rect.y as i32, 15, Color::BLACK);
+
+    let last_select = state.projection_select;
+
+    d.gui_toggle_group(
+        Rectangle::new(rect.x, rect.y + 20.0, rect.width, 25.0),
+        Some(rstr!("PCA;LDA;Slice")),
+        &mut state.projection_select,
+    );
+
+    if last_select != state.projection_select {
+        update_projection(state);
+        return;
+    }
+
+    if METHODS[state.projection_select as usize] != Method::Slice {
+        return;
+    }
+
+    let last_means = state.slice_means_select;
+
+    d.gui_toggle_group(
+        Rectangle::new(rect.x, rect.y + 55.0, rect.width, 25.0),
+        Some(rstr!("Positive;Negative;Middle")),
+        &mut state.slice_means_select,
+    );
+
+    if last_means != state.slice_means_select {
+        let features = selected_features(state);
+        let fit_data = fitting_data(state, &unscaled_train_data(state), &features);
+        state.slice_values = slice_means(&fit_data, features.len(), state.slice_means_select);
+        update_slice(state);
+    }
+
+    let axes = [state.x_feature.1 as usize, state.y_feature.1 as usize];
+    let mut moved = false;
+    let mut row = 0.0;
+
+    for feature in 0..state.slice_values.len() {
+        if axes.contains(&feature) {
+            continue;
+        }
+
+        let (min, max) = state
+            .data
+            .iter()
+            .map(|s| s.features[feature])
+            .fold((f64::MAX, f64::MIN), |(min, max), v| {
+                (min.min(v), max.max(v))
+            });
+
+        let name = CString::new(short_name(&state.csv_data.feature_names[feature])).unwrap();
+        let value_text = CString::new(format!("{:.2}", state.slice_values[feature])).unwrap();
+        let mut value = state.slice_values[feature] as f32;
+
+        d.gui_slider_bar(
+            Rectangle::new(rect.x + 40.0, rect.y + 90.0 + row * 25.0, 130.0, 20.0),
+            Some(name.as_c_str()),
+            Some(value_text.as_c_str()),
+            &mut value,
+            min as f32,
+            max as f32,
+        );
+
+        if value != state.slice_values[feature] as f32 {
+            state.slice_values[feature] = value as f64;
+            moved = true;
+        }
+
+        row += 1.0;
+    }
+
+    if moved {
+        update_slice(state);
+    }
+}
+
+// Class with the target 1
+pub fn iris_type_box(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) -> bool {
     let collision = rect.check_collision_point_rec(d.get_mouse_position());
     let pressed = d.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
 
@@ -41,26 +316,170 @@ pub fn iris_type_box(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangl
         state.iris_type_component.0 = !state.iris_type_component.0;
     }
 
+    let options = CString::new(state.csv_data.class_names.join(";")).unwrap();
+
     d.gui_dropdown_box(
         rect,
-        Some(rstr!("Virginica;Versicolor")),
+        Some(options.as_c_str()),
         &mut state.iris_type_component.1,
         state.iris_type_component.0,
     );
 
     if last_result != state.iris_type_component.1 {
         state.iris_type_component.0 = false;
-        match state.iris_type_component.1 {
-            0 => state.iris_type = Iris::Virginica,
-            1 => state.iris_type = Iris::Versicolour,
-            _ => (),
+
+        let positive = state.iris_type_component.1 as usize;
+
+        // Picking the negative class swaps the two
+        if state.negative_class == Some(positive) {
+            state.negative_class = Some(state.iris_type);
+            state.negative_component.1 = state.iris_type as i32;
+        }
+
+        state.iris_type = positive;
+        true
+    } else {
+        false
+    }
+}
+
+// Class with the target 0, or every other class
+pub fn negative_class_box(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) -> bool {
+    let collision = rect.check_collision_point_rec(d.get_mouse_position());
+    let pressed = d.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
+
+    let last_result = state.negative_component.1;
+
+    if collision && pressed {
+        state.negative_component.0 = !state.negative_component.0;
+    }
+
+    let options = CString::new(format!("{};Rest", state.csv_data.class_names.join(";"))).unwrap();
+
+    d.gui_dropdown_box(
+        rect,
+        Some(options.as_c_str()),
+        &mut state.negative_component.1,
+        state.negative_component.0,
+    );
+
+    if last_result != state.negative_component.1 {
+        state.negative_component.0 = false;
+
+        let negative = state.negative_component.1 as usize;
+
+        if negative >= state.csv_data.class_names.len() {
+            state.negative_class = None;
+        } else {
+            // Picking the positive class swaps the two
+            if negative == state.iris_type {
+                let previous = state.negative_class.unwrap_or(0);
+                state.iris_type = if previous == negative {
+                    (negative + 1) % state.csv_data.class_names.len()
+                } else {
+                    previous
+                };
+                state.iris_type_component.1 = state.iris_type as i32;
+            }
+
+            state.negative_class = Some(negative);
         }
-        Some(state.iris_type)
+
+        true
+    } else {
+        false
+    }
+}
+
+pub fn panel_box(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) -> Option<i32> {
+    let collision = rect.check_collision_point_rec(d.get_mouse_position());
+    let pressed = d.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
+
+    let last_result = state.panel_select.1;
+
+    if collision && pressed {
+        state.panel_select.0 = !state.panel_select.0;
+    }
+
+    d.gui_dropdown_box(
+        rect,
+        Some(rstr!(
+            "Test data;Cross-validation;Calibration;Dataset;Statistics;Perturbation;Imbalance;Pair plot;Parallel"
+        )),
+        &mut state.panel_select.1,
+        state.panel_select.0,
+    );
+
+    if last_result != state.panel_select.1 {
+        state.panel_select.0 = false;
+        Some(state.panel_select.1)
+    } else {
+        None
+    }
+}
+
+pub fn scaling_box(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) -> Option<i32> {
+    let collision = rect.check_collision_point_rec(d.get_mouse_position());
+    let pressed = d.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
+
+    let last_result = state.scaling_select.1;
+
+    if collision && pressed {
+        state.scaling_select.0 = !state.scaling_select.0;
+    }
+
+    d.gui_dropdown_box(
+        rect,
+        Some(rstr!("No scaling;Z-score;Min-max;Robust")),
+        &mut state.scaling_select.1,
+        state.scaling_select.0,
+    );
+
+    if last_result != state.scaling_select.1 {
+        state.scaling_select.0 = false;
+        Some(state.scaling_select.1)
     } else {
         None
     }
 }
 
+// Save and load the neuron with its scaler
+pub fn model_buttons(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) {
+    let width = rect.width / 2.0 - 5.0;
+
+    if d.gui_button(
+        Rectangle::new(rect.x, rect.y, width, rect.height),
+        Some(rstr!("Save")),
+    ) {
+        state.status = Some(match current_model(state).save(model::MODEL_PATH) {
+            Ok(()) => format!("Saved {}", model::MODEL_PATH),
+            Err(error) => error,
+        });
+    }
+
+    if d.gui_button(
+        Rectangle::new(rect.x + width + 10.0, rect.y, width, rect.height),
+        Some(rstr!("Load")),
+    ) {
+        let result = Model::load(model::MODEL_PATH).and_then(|model| apply_model(state, model));
+
+        state.status = Some(match result {
+            Ok(()) => format!("Loaded {}", model::MODEL_PATH),
+            Err(error) => error,
+        });
+    }
+
+    if let Some(status) = &state.status {
+        d.draw_text(
+            status,
+            rect.x as i32,
+            (rect.y + rect.height) as i32 + 5,
+            15,
+            Color::DARKGRAY,
+        );
+    }
+}
+
 pub fn frequency(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) {
     let collision = rect.check_collision_point_rec(d.get_mouse_position());
     let pressed = d.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
@@ -145,48 +564,28 @@ pub fn draw_table(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle)
     let font_size = 20;
     let gap = 20;
     let mut y = rect.y;
-    let biggest_size = d.measure_text("Iris Versicolor", font_size) + gap;
+    let biggest_size = state
+        .csv_data
+        .class_names
+        .iter()
+        .map(|name| d.measure_text(name, font_size))
+        .max()
+        .unwrap_or(0)
+        + gap;
     let text_size = d.measure_text("9.99", font_size);
 
-    let range = match state.iris_select.1 {
-        0 => 0..2,
-        1 => 2..4,
-        _ => 0..4,
-    };
+    let features = selected_features(state);
 
-    let mut columns = vec![
-        ("Specie", biggest_size),
-        ("SL", text_size),
-        ("SW", text_size),
-        ("PL", text_size),
-        ("PW", text_size),
-        ("O", text_size),
-        ("%", text_size),
-    ];
+    let names: Vec<String> = features
+        .iter()
+        .map(|&i| short_name(&state.csv_data.feature_names[i]))
+        .collect();
 
-    let mut x = rect.x as i32;
+    let mut columns = vec![("Specie", biggest_size)];
+    columns.extend(names.iter().map(|name| (name.as_str(), text_size)));
+    columns.extend([("O", text_size), ("%", text_size)]);
 
-    match state.iris_select.1 {
-        0 => {
-            columns = vec![
-                ("Specie", biggest_size),
-                ("SL", text_size),
-                ("SW", text_size),
-                ("O", text_size),
-                ("%", text_size),
-            ]
-        }
-        1 => {
-            columns = vec![
-                ("Specie", biggest_size),
-                ("PL", text_size),
-                ("PW", text_size),
-                ("O", text_size),
-                ("%", text_size),
-            ]
-        }
-        _ => (),
-    }
+    let mut x = rect.x as i32;
 
     for column in columns.iter() {
         d.draw_text(
@@ -200,21 +599,49 @@ pub fn draw_table(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle)
         x += column.1 + gap;
     }
 
-    for flower in state.test_data.iter() {
-        let text = flower.1.to_string();
+    // Only the rows that fit above the frequency box are listed
+    let max_rows = 11;
+    let mut correct = 0;
 
-        d.draw_text(&text, rect.x as i32, y as i32, font_size, Color::BLACK);
+    // The row of the sample pinned on the separation graph
+    let selected_row = state
+        .selected_sample
+        .and_then(|selected| state.split.test.iter().position(|&i| i == selected));
 
-        let mut size = 0;
+    for (row, flower) in state.test_data.iter().enumerate() {
+        let inputs: Vec<f64> = features.iter().map(|&i| flower.0[i]).collect();
+        let output = state
+            .calibrator
+            .apply(state.neuron.feed_forward(&state.scaler.transform(&inputs)));
+
+        if (output >= 0.5) == (flower.1 == state.iris_type) {
+            correct += 1;
+        }
+
+        if row >= max_rows {
+            continue;
+        }
+
+        if selected_row == Some(row) {
+            d.draw_rectangle(
+                rect.x as i32 - 2,
+                y as i32,
+                rect.width as i32,
+                font_size,
+                Color::new(255, 230, 120, 255),
+            );
+        }
 
-        let mut inputs: Vec<f64> = vec![];
+        let text = &state.csv_data.class_names[flower.1];
 
-        for i in range.clone() {
+        d.draw_text(text, rect.x as i32, y as i32, font_size, Color::BLACK);
+
+        let mut size = 0;
+
+        for &i in features.iter() {
             // Draw the column with the column name
             let text = format!("{:.2}", flower.0[i]);
 
-            inputs.push(flower.0[i]);
-
             d.draw_text(
                 &text,
                 rect.x as i32 + biggest_size + size as i32,
@@ -226,12 +653,14 @@ pub fn draw_table(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle)
             size += text_size + gap;
         }
 
-        let output = state.neuron.feed_forward(&inputs);
-
         let output_text = format!("{:.2}", output);
 
         // Calc the percent of the output for the target
-        let target: f64 = flower.1.into();
+        let target = if flower.1 == state.iris_type {
+            1.0
+        } else {
+            0.0
+        };
         let percent = 100.0 - (target - output).abs() * 100.0;
 
         let color = if percent < 50.0 {
@@ -260,4 +689,1330 @@ pub fn draw_table(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle)
 
         y += font_size as f32;
     }
+
+    if !state.test_data.is_empty() {
+        let hidden = state.test_data.len().saturating_sub(max_rows);
+
+        d.draw_text(
+            &format!(
+                "Test accuracy: {:.2}% ({}/{}){}",
+                100.0 * correct as f64 / state.test_data.len() as f64,
+                correct,
+                state.test_data.len(),
+                if hidden > 0 {
+                    format!(", {} rows not shown", hidden)
+                } else {
+                    "".to_owned()
+                }
+            ),
+            rect.x as i32,
+            y as i32 + 5,
+            15,
+            Color::DARKGRAY,
+        );
+    }
+}
+
+pub fn cross_validation_panel(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) {
+    let font_size = 20;
+    let gap = 20;
+
+    d.gui_spinner(
+        Rectangle::new(rect.x + 60.0, rect.y - 30.0, 100.0, 25.0),
+        Some(rstr!("Folds")),
+        &mut state.cv_folds,
+        2,
+        10,
+        false,
+    );
+
+    d.gui_check_box(
+        Rectangle::new(rect.x + 170.0, rect.y - 30.0, 25.0, 25.0),
+        Some(rstr!("Stratified")),
+        &mut state.cv_stratified,
+    );
+
+    if d.gui_button(
+        Rectangle::new(rect.x + 290.0, rect.y - 30.0, 60.0, 25.0),
+        Some(rstr!("Run")),
+    ) {
+        state.cross_validation = Some(cross_validation::cross_validate(
+            &cross_validation_data(state),
+            state.cv_folds as usize,
+            state.cv_stratified,
+            SCALINGS[state.scaling_select.1 as usize],
+            cross_validation::DEFAULT_EPOCHS,
+            cross_validation::DEFAULT_LEARNING_RATE,
+            state.split_config.seed,
+        ));
+    }
+
+    let report = match &state.cross_validation {
+        Some(Ok(report)) => report,
+        Some(Err(error)) => {
+            d.draw_text(
+                error,
+                rect.x as i32,
+                rect.y as i32 + 10,
+                font_size,
+                Color::RED,
+            );
+            return;
+        }
+        None => {
+            d.draw_text(
+                "Press Run to cross-validate",
+                rect.x as i32,
+                rect.y as i32 + 10,
+                font_size,
+                Color::GRAY,
+            );
+            return;
+        }
+    };
+
+    let name_size = d.measure_text("Precision", font_size) + gap;
+    let value_size = d.measure_text("0.0000", font_size) + gap;
+
+    let mut y = rect.y as i32 + 10;
+
+    d.draw_text(
+        &format!(
+            "{}-fold{}",
+            report.folds,
+            if report.stratified { " stratified" } else { "" }
+        ),
+        rect.x as i32,
+        y,
+        font_size,
+        Color::BLACK,
+    );
+
+    y += font_size + 5;
+
+    d.draw_text(
+        "Mean",
+        rect.x as i32 + name_size,
+        y,
+        font_size,
+        Color::BLACK,
+    );
+    d.draw_text(
+        "Std",
+        rect.x as i32 + name_size + value_size,
+        y,
+        font_size,
+        Color::BLACK,
+    );
+
+    y += font_size;
+
+    for (name, metric) in [
+        ("Accuracy", report.accuracy),
+        ("Precision", report.precision),
+        ("Recall", report.recall),
+        ("Log loss", report.log_loss),
+    ] {
+        d.draw_text(name, rect.x as i32, y, font_size, Color::BLACK);
+        d.draw_text(
+            &format!("{:.4}", metric.mean),
+            rect.x as i32 + name_size,
+            y,
+            font_size,
+            Color::BLACK,
+        );
+        d.draw_text(
+            &format!("{:.4}", metric.std),
+            rect.x as i32 + name_size + value_size,
+            y,
+            font_size,
+            Color::DARKGRAY,
+        );
+
+        y += font_size;
+    }
+}
+
+pub fn calibration_panel(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) {
+    let font_size = 20;
+    let size = 180.0;
+
+    let last_select = state.calibrator_select;
+
+    d.gui_toggle_group(
+        Rectangle::new(rect.x, rect.y - 30.0, 90.0, 25.0),
+        Some(rstr!("None;Platt;Temperature")),
+        &mut state.calibrator_select,
+    );
+
+    let refit = d.gui_button(
+        Rectangle::new(rect.x + 280.0, rect.y - 30.0, 50.0, 25.0),
+        Some(rstr!("Fit")),
+    );
+
+    if state.validation_data.is_empty() {
+        d.draw_text(
+            "No validation data",
+            rect.x as i32,
+            rect.y as i32 + 10,
+            font_size,
+            Color::GRAY,
+        );
+        return;
+    }
+
+    let validation: Vec<(Vec<f64>, f64)> = state
+        .validation_data
+        .iter()
+        .map(|(inputs, target)| (state.scaler.inverse_transform(inputs), *target))
+        .collect();
+    let raw = calibration::predictions(&current_model(state), &validation);
+
+    if refit || last_select != state.calibrator_select {
+        state.calibrator = match state.calibrator_select {
+            1 => Calibrator::fit_platt(&raw),
+            2 => Calibrator::fit_temperature(&raw),
+            _ => Calibrator::None,
+        };
+    }
+
+    let calibrated: Vec<(f64, f64)> = raw
+        .iter()
+        .map(|(p, t)| (state.calibrator.apply(*p), *t))
+        .collect();
+
+    let bins = calibration::reliability_bins(&calibrated, calibration::BINS);
+
+    // Reliability diagram: the closer the dots are to the diagonal, the better calibrated the output is
+    let graph = Rectangle::new(rect.x + 30.0, rect.y + 10.0, size, size);
+    let to_screen = |p: f64, o: f64| {
+        Vector2::new(
+            graph.x + p as f32 * graph.width,
+            graph.y + graph.height - o as f32 * graph.height,
+        )
+    };
+
+    d.draw_rectangle_lines_ex(graph, 1.0, Color::BLACK);
+    d.draw_line_ex(
+        to_screen(0.0, 0.0),
+        to_screen(1.0, 1.0),
+        1.0,
+        Color::new(0, 0, 0, 100),
+    );
+
+    let bin_width = graph.width / calibration::BINS as f32;
+
+    for (i, bin) in bins.iter().enumerate().filter(|(_, b)| b.count > 0) {
+        let height = bin.observed as f32 * graph.height;
+
+        d.draw_rectangle_rec(
+            Rectangle::new(
+                graph.x + i as f32 * bin_width + 1.0,
+                graph.y + graph.height - height,
+                bin_width - 2.0,
+                height,
+            ),
+            Color::new(0, 121, 241, 80),
+        );
+
+        d.draw_circle_v(
+            to_screen(bin.mean_predicted, bin.observed),
+            4.0,
+            Color::BLUE,
+        );
+    }
+
+    d.draw_text(
+        "0",
+        graph.x as i32 - 12,
+        (graph.y + graph.height) as i32 - 10,
+        15,
+        Color::BLACK,
+    );
+    d.draw_text("1", graph.x as i32 - 12, graph.y as i32, 15, Color::BLACK);
+    d.draw_text(
+        "Predicted",
+        (graph.x + graph.width) as i32 - d.measure_text("Predicted", 15),
+        (graph.y + graph.height) as i32 + 5,
+        15,
+        Color::BLACK,
+    );
+    d.draw_text(
+        "Observed",
+        graph.x as i32,
+        graph.y as i32 - 15,
+        15,
+        Color::BLACK,
+    );
+
+    let x = (graph.x + graph.width) as i32 + 20;
+    let mut y = graph.y as i32;
+
+    for (name, value) in [
+        ("Brier", calibration::brier_score(&calibrated)),
+        ("ECE", calibration::expected_calibration_error(&bins)),
+        ("Raw Brier", calibration::brier_score(&raw)),
+        (
+            "Raw ECE",
+            calibration::expected_calibration_error(&calibration::reliability_bins(
+                &raw,
+                calibration::BINS,
+            )),
+        ),
+    ] {
+        d.draw_text(
+            &format!("{}: {:.4}", name, value),
+            x,
+            y,
+            font_size,
+            Color::BLACK,
+        );
+        y += font_size;
+    }
+
+    y += 5;
+
+    let calibrator_text = match state.calibrator {
+        Calibrator::None => "No scaling".to_owned(),
+        Calibrator::Platt { a, b } => format!("a: {:.3} b: {:.3}", a, b),
+        Calibrator::Temperature { t } => format!("T: {:.3}", t),
+    };
+
+    d.draw_text(&calibrator_text, x, y, font_size, Color::DARKGRAY);
+    d.draw_text(
+        &format!("{} validation samples", state.validation_data.len()),
+        x,
+        y + font_size,
+        font_size,
+        Color::DARKGRAY,
+    );
+}
+
+// Switch between the dataset file and the synthetic generators
+pub fn dataset_panel(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) {
+    let font_size = 20;
+    let label_width = 70.0;
+    let slider =
+        |i: f32| Rectangle::new(rect.x + label_width, rect.y + 10.0 + i * 30.0, 150.0, 20.0);
+
+    let mut samples = state.synthetic.samples as f32;
+    let mut noise = state.synthetic.noise as f32;
+    let mut margin = state.synthetic.margin as f32;
+    let mut seed = state.synthetic.seed as i32;
+
+    d.gui_slider_bar(
+        slider(0.0),
+        Some(rstr!("Samples")),
+        None,
+        &mut samples,
+        20.0,
+        1000.0,
+    );
+    d.gui_slider_bar(
+        slider(1.0),
+        Some(rstr!("Noise")),
+        None,
+        &mut noise,
+        0.0,
+        1.0,
+    );
+    d.gui_slider_bar(
+        slider(2.0),
+        Some(rstr!("Margin")),
+        None,
+        &mut margin,
+        0.0,
+        1.0,
+    );
+    d.gui_spinner(slider(3.0), Some(rstr!("Seed")), &mut seed, 0, 9999, false);
+
+    state.synthetic.samples = samples.round() as usize;
+    state.synthetic.noise = noise as f64;
+    state.synthetic.margin = margin as f64;
+    state.synthetic.seed = seed as u64;
+
+    for (i, text) in [
+        format!("{}", state.synthetic.samples),
+        format!("{:.2}", state.synthetic.noise),
+        format!("{:.2}", state.synthetic.margin),
+    ]
+    .iter()
+    .enumerate()
+    {
+        let bounds = slider(i as f32);
+        d.draw_text(
+            text,
+            (bounds.x + bounds.width) as i32 + 10,
+            bounds.y as i32,
+            font_size,
+            Color::BLACK,
+        );
+    }
+
+    if d.gui_button(
+        Rectangle::new(rect.x + 110.0, rect.y - 30.0, 80.0, 25.0),
+        Some(rstr!("Generate")),
+    ) {
+        state.synthetic.generator = GENERATORS[state.synthetic_select.1 as usize];
+        load_dataset(state, synthetic::generate(&state.synthetic));
+        state.dataset_status = None;
+    }
+
+    if d.gui_button(
+        Rectangle::new(rect.x + 200.0, rect.y - 30.0, 80.0, 25.0),
+        Some(rstr!("Load file")),
+    ) {
+        match Dataset::load_with_fallback(&state.dataset_config) {
+            Ok(dataset) => {
+                load_dataset(state, dataset);
+                state.dataset_status = None;
+            }
+            Err(error) => state.dataset_status = Some(error.to_string()),
+        }
+    }
+
+    // Every sample, or one subset, with the predictions of the neuron
+    d.draw_text(
+        "Export",
+        rect.x as i32,
+        rect.y as i32 + 170,
+        15,
+        Color::BLACK,
+    );
+
+    if d.gui_text_box(
+        Rectangle::new(rect.x + 55.0, rect.y + 165.0, 170.0, 25.0),
+        &mut state.export_path,
+        state.export_path_edit,
+    ) {
+        state.export_path_edit = !state.export_path_edit;
+    }
+
+    // CSV, ARFF, JSON or JSON lines by the extension of the path
+    if d.gui_button(
+        Rectangle::new(rect.x + 230.0, rect.y + 165.0, 60.0, 25.0),
+        Some(rstr!("Write")),
+    ) {
+        let path = typed_path(&state.export_path);
+        let only = match state.export_rows {
+            1 => Some(Subset::Train),
+            2 => Some(Subset::Validation),
+            3 => Some(Subset::Test),
+            _ => None,
+        };
+        let rows = prediction_rows(state, only);
+
+        let result = formats::write_predictions(
+            &path,
+            &state
+                .csv_data
+                .pair_name(state.iris_type, state.negative_class),
+            &state.csv_data.feature_names,
+            &state.csv_data.class_names,
+            &rows,
+        );
+
+        state.status = Some(match result {
+            Ok(()) => format!("Exported {} rows to {}", rows.len(), path),
+            Err(error) => error,
+        });
+    }
+
+    d.gui_toggle_group(
+        Rectangle::new(rect.x + 55.0, rect.y + 195.0, 58.0, 25.0),
+        Some(rstr!("All;Train;Validation;Test")),
+        &mut state.export_rows,
+    );
+
+    // The error of the last load, or the dataset in use
+    let (status, color) = match &state.dataset_status {
+        Some(error) => (error.clone(), Color::RED),
+        None => (
+            format!("Source: {}", state.csv_data.source),
+            Color::DARKGRAY,
+        ),
+    };
+
+    d.draw_text(
+        &status,
+        rect.x as i32,
+        rect.y as i32 + 135,
+        font_size,
+        color,
+    );
+
+    // Drawn last so the open list covers the sliders
+    let dropdown = Rectangle::new(rect.x, rect.y - 30.0, 100.0, 25.0);
+
+    if dropdown.check_collision_point_rec(d.get_mouse_position())
+        && d.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+    {
+        state.synthetic_select.0 = !state.synthetic_select.0;
+    }
+
+    let last_result = state.synthetic_select.1;
+
+    d.gui_dropdown_box(
+        dropdown,
+        Some(rstr!("Blobs;XOR;Moons;Circles;Spirals;Linear")),
+        &mut state.synthetic_select.1,
+        state.synthetic_select.0,
+    );
+
+    if last_result != state.synthetic_select.1 {
+        state.synthetic_select.0 = false;
+    }
+}
+
+// White for no correlation, red for positive and blue for negative
+fn correlation_color(r: f64) -> Color {
+    let fade = (255.0 * (1.0 - r.abs().min(1.0))) as u8;
+
+    if r >= 0.0 {
+        Color::new(230, fade, fade, 255)
+    } else {
+        Color::new(fade, fade, 230, 255)
+    }
+}
+
+// Per species summary of every feature of the dataset, and how the features correlate
+pub fn statistics_panel(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) {
+    let font_size = 15;
+    let row_height = 18;
+
+    // The whole dataset, before the split, the perturbations and the edits
+    let samples = &state.csv_data.samples;
+    let classes: Vec<usize> = (0..state.csv_data.class_names.len()).collect();
+
+    if classes.is_empty() || samples.is_empty() {
+        return;
+    }
+
+    let options = CString::new(
+        classes
+            .iter()
+            .map(|&c| state.csv_data.class_names[c].as_str())
+            .collect::<Vec<&str>>()
+            .join(";"),
+    )
+    .unwrap();
+
+    d.gui_toggle_group(
+        Rectangle::new(
+            rect.x,
+            rect.y - 30.0,
+            (rect.width / classes.len() as f32 - 2.0).min(110.0),
+            25.0,
+        ),
+        Some(options.as_c_str()),
+        &mut state.stats_class,
+    );
+
+    let class = classes[(state.stats_class.max(0) as usize).min(classes.len() - 1)];
+    let features = state.csv_data.feature_names.len();
+
+    // Summary table, one row per feature
+    let name_width = 45;
+    let value_width = 42;
+    let columns = [
+        "n", "mean", "std", "min", "q1", "med", "q3", "max", "Fisher",
+    ];
+
+    let mut y = rect.y as i32 + 5;
+
+    for (i, column) in columns.iter().enumerate() {
+        d.draw_text(
+            column,
+            rect.x as i32 + name_width + i as i32 * value_width,
+            y,
+            font_size,
+            Color::DARKGRAY,
+        );
+    }
+
+    y += row_height;
+
+    for feature in 0..features {
+        d.draw_text(
+            &short_name(&state.csv_data.feature_names[feature]),
+            rect.x as i32,
+            y,
+            font_size,
+            Color::BLACK,
+        );
+
+        let Some(summary) = statistics::class_summary(samples, class, feature) else {
+            continue;
+        };
+
+        // How well the feature alone separates the positive class from every other class
+        let fisher = statistics::fisher_score(samples, feature, state.iris_type);
+
+        let mut values = vec![summary.count.to_string()];
+        values.extend(
+            [
+                summary.mean,
+                summary.std,
+                summary.min,
+                summary.q1,
+                summary.median,
+                summary.q3,
+                summary.max,
+            ]
+            .iter()
+            .map(|v| format!("{:.2}", v)),
+        );
+        values.push(format!("{:.1}", fisher));
+
+        for (i, value) in values.iter().enumerate() {
+            d.draw_text(
+                value,
+                rect.x as i32 + name_width + i as i32 * value_width,
+                y,
+                font_size,
+                if i == values.len() - 1 {
+                    Color::BLUE
+                } else {
+                    Color::BLACK
+                },
+            );
+        }
+
+        y += row_height;
+    }
+
+    // Correlation heatmap of the dataset
+    let matrix = statistics::correlation_matrix(samples, features);
+    let cell = (120 / features.max(1) as i32).min(28);
+    let top = y + 10;
+    let left = rect.x as i32 + name_width;
+
+    for (i, row) in matrix.iter().enumerate() {
+        let name = short_name(&state.csv_data.feature_names[i]);
+
+        d.draw_text(
+            &name,
+            rect.x as i32,
+            top + i as i32 * cell + (cell - font_size) / 2,
+            font_size,
+            Color::BLACK,
+        );
+        d.draw_text(
+            &name,
+            left + i as i32 * cell + 2,
+            top + features as i32 * cell + 3,
+            font_size,
+            Color::BLACK,
+        );
+
+        for (j, r) in row.iter().enumerate() {
+            let x = left + j as i32 * cell;
+            let y = top + i as i32 * cell;
+
+            d.draw_rectangle(x, y, cell, cell, correlation_color(*r));
+
+            if cell >= 25 {
+                d.draw_text(&format!("{:.1}", r), x + 3, y + 7, 12, Color::BLACK);
+            }
+        }
+    }
+
+    let legend_x = left + features as i32 * cell + 20;
+
+    d.draw_text(
+        "Pearson correlation",
+        legend_x,
+        top,
+        font_size,
+        Color::BLACK,
+    );
+
+    for (i, r) in [-1.0, -0.5, 0.0, 0.5, 1.0].iter().enumerate() {
+        let y = top + row_height + i as i32 * row_height;
+
+        d.draw_rectangle(legend_x, y, 15, 15, correlation_color(*r));
+        d.draw_text(
+            &format!("{:.1}", r),
+            legend_x + 20,
+            y,
+            font_size,
+            Color::BLACK,
+        );
+    }
+}
+
+// Noise, label flips and outliers added to the training samples
+pub fn perturbation_panel(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) {
+    let font_size = 20;
+    let label_width = 80.0;
+    let slider =
+        |i: f32| Rectangle::new(rect.x + label_width, rect.y + 10.0 + i * 30.0, 150.0, 20.0);
+
+    let mut noise = state.perturbation.noise as f32;
+    let mut flip_rate = state.perturbation.flip_rate as f32;
+    let mut outlier_rate = state.perturbation.outlier_rate as f32;
+    let mut seed = state.perturbation.seed as i32;
+
+    d.gui_slider_bar(
+        slider(0.0),
+        Some(rstr!("Noise sd")),
+        None,
+        &mut noise,
+        0.0,
+        1.0,
+    );
+    d.gui_slider_bar(
+        slider(1.0),
+        Some(rstr!("Label flips")),
+        None,
+        &mut flip_rate,
+        0.0,
+        0.5,
+    );
+    d.gui_slider_bar(
+        slider(2.0),
+        Some(rstr!("Outliers")),
+        None,
+        &mut outlier_rate,
+        0.0,
+        0.2,
+    );
+    d.gui_spinner(slider(3.0), Some(rstr!("Seed")), &mut seed, 0, 9999, false);
+
+    state.perturbation.noise = noise as f64;
+    state.perturbation.flip_rate = flip_rate as f64;
+    state.perturbation.outlier_rate = outlier_rate as f64;
+    state.perturbation.seed = seed as u64;
+
+    for (i, text) in [
+        format!("{:.2}", state.perturbation.noise),
+        format!("{:.0}%", state.perturbation.flip_rate * 100.0),
+        format!("{:.0}%", state.perturbation.outlier_rate * 100.0),
+    ]
+    .iter()
+    .enumerate()
+    {
+        let bounds = slider(i as f32);
+        d.draw_text(
+            text,
+            (bounds.x + bounds.width) as i32 + 10,
+            bounds.y as i32,
+            font_size,
+            Color::BLACK,
+        );
+    }
+
+    // The neuron is trained again from scratch on the perturbed data
+    if d.gui_button(
+        Rectangle::new(rect.x, rect.y - 30.0, 80.0, 25.0),
+        Some(rstr!("Apply")),
+    ) {
+        update_data_type(state, state.iris_select.1);
+    }
+
+    if d.gui_button(
+        Rectangle::new(rect.x + 90.0, rect.y - 30.0, 80.0, 25.0),
+        Some(rstr!("Clean")),
+    ) {
+        state.perturbation.noise = 0.0;
+        state.perturbation.flip_rate = 0.0;
+        state.perturbation.outlier_rate = 0.0;
+        update_data_type(state, state.iris_select.1);
+    }
+
+    let mut y = rect.y as i32 + 135;
+
+    for (corruption, name, color) in [
+        (Corruption::Noise, "noisy", Color::LIGHTGRAY),
+        (Corruption::Flip, "flipped", Color::BLACK),
+        (Corruption::Outlier, "outliers", Color::MAGENTA),
+    ] {
+        let count = state
+            .corruptions
+            .iter()
+            .filter(|&&c| c == corruption)
+            .count();
+
+        d.draw_circle_lines(rect.x as i32 + 8, y + 10, 8.0, color);
+        d.draw_text(
+            &format!("{} {}", count, name),
+            rect.x as i32 + 25,
+            y,
+            font_size,
+            Color::BLACK,
+        );
+
+        y += font_size + 5;
+    }
+}
+
+// Make one class rare in the training split and compare the ways to make up for it
+pub fn imbalance_panel(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) {
+    let font_size = 20;
+    let label_width = 100.0;
+    let row = |i: f32, width: f32| {
+        Rectangle::new(rect.x + label_width, rect.y + 10.0 + i * 30.0, width, 20.0)
+    };
+
+    let mut classes: Vec<usize> = state.data.iter().map(|s| s.class).collect();
+    classes.sort();
+    classes.dedup();
+
+    let short_names = unique_short_names(&state.csv_data.class_names);
+    let downsample_options = CString::new(format!(
+        "Off;{}",
+        classes
+            .iter()
+            .map(|&c| short_names[c].as_str())
+            .collect::<Vec<&str>>()
+            .join(";")
+    ))
+    .unwrap();
+
+    let mut keep_ratio = state.imbalance.keep_ratio as f32;
+    let mut negative_weight = state.imbalance.manual_weights[0] as f32;
+    let mut positive_weight = state.imbalance.manual_weights[1] as f32;
+
+    for (i, label) in [
+        "Downsample",
+        "Keep",
+        "Resampling",
+        "Weights",
+        "Weight 0",
+        "Weight 1",
+    ]
+    .iter()
+    .enumerate()
+    {
+        d.draw_text(
+            label,
+            rect.x as i32,
+            row(i as f32, 0.0).y as i32,
+            15,
+            Color::BLACK,
+        );
+    }
+
+    d.gui_toggle_group(
+        row(0.0, 60.0),
+        Some(downsample_options.as_c_str()),
+        &mut state.downsample_select,
+    );
+    d.gui_slider_bar(row(1.0, 150.0), None, None, &mut keep_ratio, 0.05, 1.0);
+    d.gui_toggle_group(
+        row(2.0, 90.0),
+        Some(rstr!("None;Oversample;Undersample")),
+        &mut state.resampling_select,
+    );
+    d.gui_toggle_group(
+        row(3.0, 90.0),
+        Some(rstr!("None;Manual;Balanced")),
+        &mut state.weighting_select,
+    );
+    d.gui_slider_bar(row(4.0, 150.0), None, None, &mut negative_weight, 0.1, 10.0);
+    d.gui_slider_bar(row(5.0, 150.0), None, None, &mut positive_weight, 0.1, 10.0);
+
+    state.imbalance.keep_ratio = keep_ratio as f64;
+    state.imbalance.manual_weights = [negative_weight as f64, positive_weight as f64];
+
+    for (i, text) in [
+        (1.0, format!("{:.0}%", keep_ratio * 100.0)),
+        (4.0, format!("{:.2}", negative_weight)),
+        (5.0, format!("{:.2}", positive_weight)),
+    ] {
+        let bounds = row(i, 150.0);
+        d.draw_text(
+            &text,
+            (bounds.x + bounds.width) as i32 + 10,
+            bounds.y as i32,
+            15,
+            Color::BLACK,
+        );
+    }
+
+    if d.gui_button(
+        Rectangle::new(rect.x, rect.y - 30.0, 80.0, 25.0),
+        Some(rstr!("Apply")),
+    ) {
+        state.imbalance.downsample_class = match state.downsample_select {
+            0 => None,
+            i => classes.get(i as usize - 1).copied(),
+        };
+        state.imbalance.resampling = match state.resampling_select {
+            1 => Resampling::Oversample,
+            2 => Resampling::Undersample,
+            _ => Resampling::None,
+        };
+        state.imbalance.weighting = match state.weighting_select {
+            1 => Weighting::Manual,
+            2 => Weighting::Balanced,
+            _ => Weighting::None,
+        };
+
+        update_data_type(state, state.iris_select.1);
+    }
+
+    // What the neuron is trained on
+    let counts = imbalance::target_counts(&state.train_data);
+
+    d.draw_text(
+        &format!(
+            "Train: {} x 0, {} x 1   weights {:.2} / {:.2}",
+            counts[0], counts[1], state.neuron.class_weights[0], state.neuron.class_weights[1]
+        ),
+        rect.x as i32,
+        rect.y as i32 + 190,
+        font_size,
+        Color::DARKGRAY,
+    );
+}
+
+fn control_down(d: &RaylibDrawHandle) -> bool {
+    d.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) || d.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL)
+}
+
+// Ctrl and a click on a point pins its sample, on the background of the graph it unpins it.
+// A plain click is left to the graph editor.
+pub fn point_selection(d: &mut RaylibDrawHandle, state: &mut State) {
+    let mouse = d.get_mouse_position();
+
+    if state.panning
+        || !control_down(d)
+        || !state.separation_graph.contains(mouse)
+        || !d.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+    {
+        return;
+    }
+
+    state.selected_sample = state.separation_graph.point_at(mouse).map(|p| p.index);
+}
+
+// The sample under the mouse, or the pinned one, with its features and the output of the neuron
+pub fn point_tooltip(d: &mut RaylibDrawHandle, state: &State) {
+    let mouse = d.get_mouse_position();
+    let graph = &state.separation_graph;
+
+    let hovered = if state.panning || state.dragging.is_some() || !graph.contains(mouse) {
+        None
+    } else {
+        graph.point_at(mouse).map(|p| (p.index, mouse))
+    };
+
+    let Some((index, position)) = hovered.or_else(|| {
+        state
+            .selected_sample
+            .and_then(|index| graph.screen_position(index).map(|p| (index, p)))
+    }) else {
+        return;
+    };
+
+    let Some(sample) = state.data.get(index) else {
+        return;
+    };
+
+    let (probability, loss) = sample_prediction(state, index);
+    let row = match sample.row {
+        Some(row) => format!("Row {}", row),
+        None => "Added sample".to_owned(),
+    };
+
+    let mut lines = vec![
+        match state.split.subsets.get(index) {
+            Some(subset) => format!("{} ({})", row, subset.name()),
+            None => row,
+        },
+        state.csv_data.class_names[sample.class].clone(),
+    ];
+    lines.extend(
+        state
+            .csv_data
+            .feature_names
+            .iter()
+            .zip(sample.features.iter())
+            .map(|(name, value)| format!("{}: {:.2}", short_name(name), value)),
+    );
+    lines.push(format!("Probability: {:.3}", probability));
+    lines.push(match loss {
+        Some(loss) => format!("Loss: {:.3}", loss),
+        None => "Loss: -".to_owned(),
+    });
+
+    let font_size = 15;
+    let width = lines
+        .iter()
+        .map(|line| d.measure_text(line, font_size))
+        .max()
+        .unwrap_or(0)
+        + 10;
+    let height = lines.len() as i32 * (font_size + 2) + 8;
+
+    // Next to the point, kept inside the window
+    let x = (position.x as i32 + 12).min(WINDOW_WIDTH as i32 - width);
+    let y = (position.y as i32 + 12).min(WINDOW_HEIGHT as i32 - height);
+
+    d.draw_rectangle(x, y, width, height, Color::new(255, 255, 240, 235));
+    d.draw_rectangle_lines(x, y, width, height, Color::DARKGRAY);
+
+    for (i, line) in lines.iter().enumerate() {
+        d.draw_text(
+            line,
+            x + 5,
+            y + 4 + i as i32 * (font_size + 2),
+            font_size,
+            Color::BLACK,
+        );
+    }
+}
+
+// Build the data on the separation graph: a left click adds a sample of the positive class and
+// a right click one of the negative class. Samples are dragged with the left button and deleted
+// with the right one.
+pub fn graph_editor(d: &mut RaylibDrawHandle, state: &mut State) {
+    // Nothing to edit when the graph doesn't show the features of the neuron
+    if state.neuron.inputs != 2 {
+        state.dragging = None;
+        return;
+    }
+
+    let mouse = d.get_mouse_position();
+    let value = state.separation_graph.get_value(mouse);
+
+    // Shift and left click pans the view instead, see graph_view_controls
+    if state.panning
+        || d.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)
+        || d.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT)
+    {
+        return;
+    }
+
+    if let Some(index) = state.dragging {
+        if d.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) {
+            state.dragging = None;
+        } else if d.get_mouse_delta() != Vector2::zero() {
+            move_sample(state, index, value.x as f64, value.y as f64);
+        }
+        return;
+    }
+
+    // Ctrl and a click pins a sample instead, see point_selection
+    if !state.separation_graph.contains(mouse) || control_down(d) {
+        return;
+    }
+
+    let point = state.separation_graph.point_at(mouse).map(|p| p.index);
+
+    if d.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+        match point {
+            Some(index) => state.dragging = Some(index),
+            None => add_sample(state, value.x as f64, value.y as f64, true),
+        }
+    } else if d.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) {
+        match point {
+            Some(index) => remove_sample(state, index),
+            None => add_sample(state, value.x as f64, value.y as f64, false),
+        }
+    }
+}
+
+// Scatter plot of every pair of features, with the histogram of each class on the diagonal.
+// Clicking a plot trains the neuron on its pair, X being the column and Y the row.
+pub fn pair_plot_panel(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) {
+    let features = state.csv_data.feature_names.len();
+
+    // Every species of the dataset, the selected pair is only highlighted
+    let samples = &state.csv_data.samples;
+
+    if samples.is_empty() || features == 0 {
+        return;
+    }
+
+    let gap = 4.0;
+    let size = ((rect.height - 40.0) - gap * (features - 1) as f32) / features as f32;
+    let left = rect.x + 20.0;
+    let top = rect.y - 20.0;
+
+    let ranges: Vec<(f64, f64)> = (0..features)
+        .map(|feature| {
+            samples
+                .iter()
+                .map(|s| s.features[feature])
+                .fold((f64::MAX, f64::MIN), |(min, max), v| {
+                    (min.min(v), max.max(v))
+                })
+        })
+        .collect();
+
+    let classes: Vec<usize> = (0..state.csv_data.class_names.len()).collect();
+
+    let active = state.iris_select.1 != ALL_FEATURES;
+    let selected_sample = state.selected_sample.and_then(|i| state.data.get(i));
+    let mouse = d.get_mouse_position();
+    let clicked = d.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
+    let mut selected = None;
+
+    for row in 0..features {
+        for column in 0..features {
+            let cell = Rectangle::new(
+                left + column as f32 * (size + gap),
+                top + row as f32 * (size + gap),
+                size,
+                size,
+            );
+
+            // Position of a value in the cell, along the range of its feature
+            let along = |feature: usize, value: f64| -> f32 {
+                let (min, max) = ranges[feature];
+                ((value - min) / (max - min).max(f64::EPSILON)) as f32
+            };
+
+            if row == column {
+                let bins = 10;
+                let counts: Vec<Vec<usize>> = classes
+                    .iter()
+                    .map(|&class| {
+                        let values: Vec<f64> = samples
+                            .iter()
+                            .filter(|s| s.class == class)
+                            .map(|s| s.features[row])
+                            .collect();
+                        statistics::histogram(&values, ranges[row].0, ranges[row].1, bins)
+                    })
+                    .collect();
+                let highest = counts.iter().flatten().copied().max().unwrap_or(1).max(1);
+                let bar_width = size / bins as f32;
+
+                for (class, counts) in classes.iter().zip(counts.iter()) {
+                    for (bin, &count) in counts.iter().enumerate() {
+                        let height = count as f32 / highest as f32 * (size - 15.0);
+
+                        d.draw_rectangle_rec(
+                            Rectangle::new(
+                                cell.x + bin as f32 * bar_width,
+                                cell.y + size - height,
+                                bar_width,
+                                height,
+                            ),
+                            class_color(*class).fade(0.4),
+                        );
+                    }
+                }
+
+                d.draw_text(
+                    &short_name(&state.csv_data.feature_names[row]),
+                    cell.x as i32 + 2,
+                    cell.y as i32 + 2,
+                    10,
+                    Color::BLACK,
+                );
+            } else {
+                for sample in samples.iter() {
+                    let x = cell.x + along(column, sample.features[column]) * (size - 4.0) + 2.0;
+                    let y = cell.y + size - 2.0 - along(row, sample.features[row]) * (size - 4.0);
+
+                    d.draw_circle_v(Vector2::new(x, y), 1.5, class_color(sample.class));
+                }
+
+                if let Some(sample) = selected_sample {
+                    let x = cell.x + along(column, sample.features[column]) * (size - 4.0) + 2.0;
+                    let y = cell.y + size - 2.0 - along(row, sample.features[row]) * (size - 4.0);
+
+                    d.draw_circle_lines(x as i32, y as i32, 4.0, Color::BLACK);
+                }
+
+                if clicked && cell.check_collision_point_rec(mouse) {
+                    selected = Some((column, row));
+                }
+            }
+
+            let is_active =
+                active && state.x_feature.1 as usize == column && state.y_feature.1 as usize == row;
+
+            d.draw_rectangle_lines_ex(
+                cell,
+                if is_active { 2.0 } else { 1.0 },
+                if is_active {
+                    Color::BLUE
+                } else {
+                    Color::LIGHTGRAY
+                },
+            );
+        }
+    }
+
+    if let Some((x, y)) = selected {
+        state.x_feature.1 = x as i32;
+        state.y_feature.1 = y as i32;
+
+        update_data_type(state, pair_data_type(state));
+    }
+}
+
+// One vertical axis per feature and one line per sample. Dragging along an axis brushes a range,
+// and only the samples inside every range are highlighted, here and on the separation graph.
+pub fn parallel_coordinates_panel(d: &mut RaylibDrawHandle, state: &mut State, rect: Rectangle) {
+    let features = state.csv_data.feature_names.len();
+
+    if state.data.is_empty() || features < 2 {
+        return;
+    }
+
+    if state.brushes.len() != features {
+        state.brushes = vec![None; features];
+    }
+
+    d.gui_toggle_group(
+        Rectangle::new(rect.x, rect.y - 30.0, 80.0, 25.0),
+        Some(rstr!("Species;Probability")),
+        &mut state.parallel_color,
+    );
+
+    if d.gui_button(
+        Rectangle::new(rect.x + 220.0, rect.y - 30.0, 60.0, 25.0),
+        Some(rstr!("Clear")),
+    ) {
+        state.brushes = vec![None; features];
+    }
+
+    let top = rect.y + 10.0;
+    let height = 210.0;
+    let left = rect.x + 20.0;
+    let spacing = (rect.width - 40.0) / (features - 1) as f32;
+    let axis_x = |feature: usize| left + feature as f32 * spacing;
+
+    let ranges: Vec<(f64, f64)> = (0..features)
+        .map(|feature| {
+            state
+                .data
+                .iter()
+                .map(|s| s.features[feature])
+                .fold((f64::MAX, f64::MIN), |(min, max), v| {
+                    (min.min(v), max.max(v))
+                })
+        })
+        .collect();
+
+    let to_y = |feature: usize, value: f64| -> f32 {
+        let (min, max) = ranges[feature];
+        top + height - ((value - min) / (max - min).max(f64::EPSILON)) as f32 * height
+    };
+    let to_value = |feature: usize, y: f32| -> f64 {
+        let (min, max) = ranges[feature];
+        min + ((top + height - y.clamp(top, top + height)) / height) as f64 * (max - min)
+    };
+
+    // Brushing
+    let mouse = d.get_mouse_position();
+
+    if let Some((feature, start)) = state.brush_drag {
+        let end = to_value(feature, mouse.y);
+
+        // A click without a drag clears the brush of the axis
+        state.brushes[feature] = if (to_y(feature, end) - to_y(feature, start)).abs() < 3.0 {
+            None
+        } else {
+            Some((start.min(end), start.max(end)))
+        };
+
+        if d.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) {
+            state.brush_drag = None;
+        }
+    } else if d.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+        if let Some(feature) = (0..features).find(|&feature| {
+            (mouse.x - axis_x(feature)).abs() <= 8.0 && mouse.y >= top && mouse.y <= top + height
+        }) {
+            state.brush_drag = Some((feature, to_value(feature, mouse.y)));
+        }
+    }
+
+    // Lines, the brushed ones last so they are on top
+    let predict = predictor(state);
+    let probabilities: Vec<f64> = state.data.iter().map(predict).collect();
+    let brushed = brushed_samples(state);
+
+    for highlighted in [false, true] {
+        for (i, sample) in state.data.iter().enumerate() {
+            let inside = brushed.as_ref().is_none_or(|brushed| brushed[i]);
+
+            if inside != highlighted {
+                continue;
+            }
+
+            let color = if !inside {
+                Color::new(200, 200, 200, 80)
+            } else if state.parallel_color == 1 {
+                probability_color(probabilities[i])
+            } else {
+                class_color(sample.class).fade(0.6)
+            };
+
+            for feature in 1..features {
+                d.draw_line_ex(
+                    Vector2::new(
+                        axis_x(feature - 1),
+                        to_y(feature - 1, sample.features[feature - 1]),
+                    ),
+                    Vector2::new(axis_x(feature), to_y(feature, sample.features[feature])),
+                    1.0,
+                    color,
+                );
+            }
+        }
+    }
+
+    // The sample pinned on the separation graph, over the others
+    if let Some(sample) = state.selected_sample.and_then(|i| state.data.get(i)) {
+        for feature in 1..features {
+            d.draw_line_ex(
+                Vector2::new(
+                    axis_x(feature - 1),
+                    to_y(feature - 1, sample.features[feature - 1]),
+                ),
+                Vector2::new(axis_x(feature), to_y(feature, sample.features[feature])),
+                3.0,
+                Color::BLACK,
+            );
+        }
+    }
+
+    // Axes, with their brushes and the range of the feature
+    for (feature, &(min, max)) in ranges.iter().enumerate() {
+        let x = axis_x(feature);
+
+        if let Some((low, high)) = state.brushes[feature] {
+            d.draw_rectangle_rec(
+                Rectangle::new(
+                    x - 5.0,
+                    to_y(feature, high),
+                    10.0,
+                    to_y(feature, low) - to_y(feature, high),
+                ),
+                Color::new(0, 0, 0, 60),
+            );
+        }
+
+        d.draw_line_ex(
+            Vector2::new(x, top),
+            Vector2::new(x, top + height),
+            1.0,
+            Color::BLACK,
+        );
+
+        let name = short_name(&state.csv_data.feature_names[feature]);
+        let name_width = d.measure_text(&name, 15);
+
+        d.draw_text(
+            &name,
+            x as i32 - name_width / 2,
+            (top + height) as i32 + 18,
+            15,
+            Color::BLACK,
+        );
+
+        for (value, y) in [(max, top - 12.0), (min, top + height + 3.0)] {
+            let text = format!("{:.1}", value);
+            let width = d.measure_text(&text, 10);
+
+            d.draw_text(&text, x as i32 - width / 2, y as i32, 10, Color::DARKGRAY);
+        }
+    }
 }
@@ -0,0 +1,272 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::{
+    neuron,
+    preprocessing::{Scaler, Scaling},
+};
+
+pub const DEFAULT_FOLDS: usize = 5;
+pub const DEFAULT_EPOCHS: usize = 100;
+pub const DEFAULT_LEARNING_RATE: f64 = 0.5;
+
+// Keeps ln() away from 0 when the neuron is fully saturated
+const EPSILON: f64 = 1e-12;
+
+#[derive(Debug, Clone, Copy)]
+pub struct FoldMetrics {
+    pub accuracy: f64,
+    pub precision: f64,
+    pub recall: f64,
+    pub log_loss: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Metric {
+    pub mean: f64,
+    pub std: f64,
+}
+
+impl Metric {
+    fn from_values(values: &[f64]) -> Self {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+
+        Self {
+            mean,
+            std: variance.sqrt(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CrossValidationReport {
+    pub folds: usize,
+    pub stratified: bool,
+    pub fold_metrics: Vec<FoldMetrics>,
+    pub accuracy: Metric,
+    pub precision: Metric,
+    pub recall: Metric,
+    pub log_loss: Metric,
+}
+
+// Split the indexes 0..len in k folds of (almost) the same size. The same seed gives the same
+// folds.
+pub fn k_fold(len: usize, k: usize, seed: u64) -> Vec<Vec<usize>> {
+    let mut indexes: Vec<usize> = (0..len).collect();
+    indexes.shuffle(&mut StdRng::seed_from_u64(seed));
+
+    let mut folds = vec![vec![]; k];
+
+    for (i, index) in indexes.into_iter().enumerate() {
+        folds[i % k].push(index);
+    }
+
+    folds
+}
+
+// Same as k_fold, but each class is dealt separately so every fold keeps the class proportions
+pub fn stratified_k_fold(targets: &[f64], k: usize, seed: u64) -> Vec<Vec<usize>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut positives: Vec<usize> = (0..targets.len()).filter(|&i| targets[i] >= 0.5).collect();
+    let mut negatives: Vec<usize> = (0..targets.len()).filter(|&i| targets[i] < 0.5).collect();
+
+    positives.shuffle(&mut rng);
+    negatives.shuffle(&mut rng);
+
+    let mut folds = vec![vec![]; k];
+
+    // The negatives continue where the positives stopped, so the fold sizes stay balanced
+    for (i, index) in positives.into_iter().chain(negatives).enumerate() {
+        folds[i % k].push(index);
+    }
+
+    folds
+}
+
+pub fn evaluate(neuron: &mut neuron::Neuron, data: &[(Vec<f64>, f64)]) -> FoldMetrics {
+    let mut true_positives = 0.0;
+    let mut false_positives = 0.0;
+    let mut false_negatives = 0.0;
+    let mut correct = 0.0;
+    let mut loss = 0.0;
+
+    for (inputs, target) in data {
+        let output = neuron.feed_forward(inputs);
+        let predicted = output >= 0.5;
+        let actual = *target >= 0.5;
+
+        match (predicted, actual) {
+            (true, true) => true_positives += 1.0,
+            (true, false) => false_positives += 1.0,
+            (false, true) => false_negatives += 1.0,
+            (false, false) => {}
+        }
+
+        if predicted == actual {
+            correct += 1.0;
+        }
+
+        let output = output.clamp(EPSILON, 1.0 - EPSILON);
+        loss -= target * output.ln() + (1.0 - target) * (1.0 - output).ln();
+    }
+
+    let precision = if true_positives + false_positives > 0.0 {
+        true_positives / (true_positives + false_positives)
+    } else {
+        0.0
+    };
+
+    let recall = if true_positives + false_negatives > 0.0 {
+        true_positives / (true_positives + false_negatives)
+    } else {
+        0.0
+    };
+
+    FoldMetrics {
+        accuracy: correct / data.len() as f64,
+        precision,
+        recall,
+        log_loss: loss / data.len() as f64,
+    }
+}
+
+// Train a new neuron for each fold on the other k - 1 folds and evaluate it on the fold left out.
+// The scaler is fitted on the training folds only.
+pub fn cross_validate(
+    data: &[(Vec<f64>, f64)],
+    k: usize,
+    stratified: bool,
+    scaling: Scaling,
+    epochs: usize,
+    learning_rate: f64,
+    seed: u64,
+) -> Result<CrossValidationReport, String> {
+    if k < 2 {
+        return Err("Cross-validation needs at least 2 folds".to_owned());
+    }
+
+    if data.len() < k {
+        return Err(format!("Only {} samples for {} folds", data.len(), k));
+    }
+
+    let folds = if stratified {
+        let targets: Vec<f64> = data.iter().map(|(_, target)| *target).collect();
+        stratified_k_fold(&targets, k, seed)
+    } else {
+        k_fold(data.len(), k, seed)
+    };
+
+    let inputs = data[0].0.len();
+
+    // The initial weights of the neurons follow the seed too, so the report can be reproduced
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut fold_metrics = vec![];
+
+    for (i, fold) in folds.iter().enumerate() {
+        let train: Vec<(Vec<f64>, f64)> = folds
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .flat_map(|(_, f)| f.iter().map(|&index| data[index].clone()))
+            .collect();
+
+        let validation: Vec<(Vec<f64>, f64)> =
+            fold.iter().map(|&index| data[index].clone()).collect();
+
+        let scaler = Scaler::fit(scaling, &train, inputs);
+        let train = scaler.transform_data(&train);
+        let validation = scaler.transform_data(&validation);
+
+        let mut neuron = neuron::Neuron::with_rng(inputs, neuron::SIGMOID, learning_rate, &mut rng);
+
+        for _ in 0..epochs {
+            for (inputs, target) in train.iter() {
+                let output = neuron.feed_forward(inputs);
+                neuron.back_propagate(output, *target);
+            }
+        }
+
+        fold_metrics.push(evaluate(&mut neuron, &validation));
+    }
+
+    let metric = |f: fn(&FoldMetrics) -> f64| {
+        Metric::from_values(&fold_metrics.iter().map(f).collect::<Vec<f64>>())
+    };
+
+    Ok(CrossValidationReport {
+        folds: k,
+        stratified,
+        accuracy: metric(|m| m.accuracy),
+        precision: metric(|m| m.precision),
+        recall: metric(|m| m.recall),
+        log_loss: metric(|m| m.log_loss),
+        fold_metrics,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every index is in exactly one fold
+    fn assert_partition(folds: &[Vec<usize>], len: usize) {
+        let mut all: Vec<usize> = folds.iter().flatten().copied().collect();
+        all.sort();
+        assert_eq!(all, (0..len).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn k_fold_partitions_indexes() {
+        let folds = k_fold(23, 5, 1);
+
+        assert_eq!(folds.len(), 5);
+        assert_partition(&folds, 23);
+        for fold in folds.iter() {
+            assert!(fold.len() == 4 || fold.len() == 5);
+        }
+    }
+
+    #[test]
+    fn stratified_k_fold_keeps_class_proportions() {
+        // 20 positives and 30 negatives
+        let targets: Vec<f64> = (0..50).map(|i| if i % 5 < 2 { 1.0 } else { 0.0 }).collect();
+        let folds = stratified_k_fold(&targets, 5, 1);
+
+        assert_eq!(folds.len(), 5);
+        assert_partition(&folds, 50);
+        for fold in folds.iter() {
+            let positives = fold.iter().filter(|&&i| targets[i] >= 0.5).count();
+            assert_eq!(fold.len(), 10);
+            assert_eq!(positives, 4);
+        }
+    }
+
+    #[test]
+    fn too_few_samples_for_folds() {
+        let data = vec![
+            (vec![1.0, 2.0], 1.0),
+            (vec![2.0, 1.0], 0.0),
+            (vec![0.0, 0.0], 0.0),
+        ];
+
+        for rows in [&data[..], &[]] {
+            assert!(cross_validate(rows, 5, true, Scaling::Standard, 1, 0.5, 1).is_err());
+        }
+        assert!(cross_validate(&data, 1, false, Scaling::None, 1, 0.5, 1).is_err());
+        assert!(cross_validate(&data, 3, false, Scaling::None, 1, 0.5, 1).is_ok());
+    }
+
+    #[test]
+    fn same_seed_gives_same_folds() {
+        let targets: Vec<f64> = (0..30).map(|i| (i % 2) as f64).collect();
+
+        assert_eq!(k_fold(30, 5, 7), k_fold(30, 5, 7));
+        assert_ne!(k_fold(30, 5, 7), k_fold(30, 5, 8));
+        assert_eq!(
+            stratified_k_fold(&targets, 5, 7),
+            stratified_k_fold(&targets, 5, 7)
+        );
+    }
+}
@@ -0,0 +1,716 @@
+use std::{fmt::Display, io::Read};
+
+use raylib::color::Color;
+
+use crate::{
+    formats::{self, Format},
+    perturbation::Corruption,
+    separation_graph::{GraphPoint, Marker},
+    split::Subset,
+};
+
+// Used when no dataset is given on the command line
+pub const DEFAULT_PATH: &str = "./iris.csv";
+// Environment variable with the path of the dataset, used when none is given on the command line
+pub const PATH_VARIABLE: &str = "IRIS_DATASET";
+
+// Copies built into the binary, so it runs from any directory
+const EMBEDDED: [(&str, &str); 2] = [
+    ("iris.csv", include_str!("../iris.csv")),
+    ("Iris-old.csv", include_str!("../Iris-old.csv")),
+];
+
+#[derive(Debug, Clone)]
+pub struct DatasetConfig {
+    pub path: String,
+    // Set when the path was given by the user, a missing file is then an error instead of a
+    // reason to use another source
+    pub path_given: bool,
+    // Columns are given by their header name or by their index (starting at 0).
+    // No feature columns means every column except the label and an "Id" column.
+    pub feature_columns: Vec<String>,
+    // None means the last column, skipping the outputs of an export
+    pub label_column: Option<String>,
+    // Raw label -> class name. Labels without a mapping keep their raw value.
+    pub label_map: Vec<(String, String)>,
+    // None means auto-detect
+    pub has_header: Option<bool>,
+    // Skip the rows that can't be read instead of failing
+    pub tolerant: bool,
+}
+
+impl Default for DatasetConfig {
+    fn default() -> Self {
+        let names = [
+            ("setosa", "Iris Setosa"),
+            ("versicolor", "Iris Versicolor"),
+            ("virginica", "Iris Virginica"),
+            ("Iris-setosa", "Iris Setosa"),
+            ("Iris-versicolor", "Iris Versicolor"),
+            ("Iris-virginica", "Iris Virginica"),
+        ];
+
+        Self {
+            path: DEFAULT_PATH.to_owned(),
+            path_given: false,
+            feature_columns: vec![],
+            label_column: None,
+            label_map: names
+                .iter()
+                .map(|(raw, name)| (raw.to_string(), name.to_string()))
+                .collect(),
+            has_header: None,
+            tolerant: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DatasetErrorReason {
+    Io(std::io::Error),
+    Csv(csv::Error),
+    ColumnNotFound(String),
+    MissingField,
+    InvalidNumber(String),
+    NotEnoughFeatures(usize),
+    NotEnoughClasses(usize),
+    Format(String),
+    UnknownLabel(String),
+    EmptyFile,
+}
+
+#[derive(Debug)]
+pub struct DatasetError {
+    pub file: String,
+    pub line: Option<u64>,
+    pub column: Option<String>,
+    pub reason: DatasetErrorReason,
+}
+
+impl Display for DatasetErrorReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DatasetErrorReason::Io(error) => write!(f, "{}", error),
+            DatasetErrorReason::Csv(error) => write!(f, "{}", error),
+            DatasetErrorReason::ColumnNotFound(column) => {
+                write!(f, "column '{}' not found", column)
+            }
+            DatasetErrorReason::MissingField => write!(f, "missing field"),
+            DatasetErrorReason::InvalidNumber(value) => write!(f, "invalid number '{}'", value),
+            DatasetErrorReason::NotEnoughFeatures(count) => {
+                write!(f, "at least 2 feature columns are needed, found {}", count)
+            }
+            DatasetErrorReason::NotEnoughClasses(count) => {
+                write!(f, "at least 2 classes are needed, found {}", count)
+            }
+            DatasetErrorReason::Format(message) => write!(f, "{}", message),
+            DatasetErrorReason::UnknownLabel(label) => {
+                write!(f, "label '{}' isn't one of the declared values", label)
+            }
+            DatasetErrorReason::EmptyFile => write!(f, "empty file"),
+        }
+    }
+}
+
+// Formatted as file:line: column 'name': reason
+impl Display for DatasetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.file)?;
+
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+
+        if let Some(column) = &self.column {
+            write!(f, ": column '{}'", column)?;
+        }
+
+        write!(f, ": {}", self.reason)
+    }
+}
+
+impl std::error::Error for DatasetError {}
+
+// Index of the bad column and what is wrong with it
+type RowError = (Option<usize>, DatasetErrorReason);
+
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub features: Vec<f64>,
+    pub class: usize,
+    // Position of the sample in the dataset it was read from, None for a sample added by hand
+    pub row: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct Dataset {
+    pub feature_names: Vec<String>,
+    // Classes are numbered in the order they first appear in the file
+    pub class_names: Vec<String>,
+    pub samples: Vec<Sample>,
+    // Rows left out in tolerant mode
+    pub skipped: Vec<DatasetError>,
+    // Where the samples come from, e.g. the path of the file
+    pub source: String,
+}
+
+fn is_number(field: &str) -> bool {
+    field.trim().parse::<f64>().is_ok()
+}
+
+// The first row is a header when one of its fields is text where the second row has a number
+fn detect_header(records: &[csv::StringRecord]) -> bool {
+    match records {
+        [first, second, ..] => first
+            .iter()
+            .zip(second.iter())
+            .any(|(a, b)| !is_number(a) && is_number(b)),
+        [first] => !first.iter().any(is_number),
+        [] => false,
+    }
+}
+
+fn column_index(column: &str, names: &[String]) -> Option<usize> {
+    names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(column))
+        .or_else(|| column.parse::<usize>().ok().filter(|i| *i < names.len()))
+}
+
+impl Dataset {
+    pub fn load(config: &DatasetConfig) -> Result<Dataset, DatasetError> {
+        let file = std::fs::File::open(&config.path).map_err(|error| DatasetError {
+            file: config.path.clone(),
+            line: None,
+            column: None,
+            reason: DatasetErrorReason::Io(error),
+        })?;
+
+        // The format is picked from the extension, CSV by default
+        match Format::from_path(&config.path) {
+            Format::Csv => Dataset::read(file, config),
+            Format::Arff => formats::read_arff(file, config),
+            Format::Json => formats::read_json(file, config),
+        }
+    }
+
+    // A path given by the user is loaded as it is. Otherwise try the path of the IRIS_DATASET
+    // variable, then the default one, then the embedded copy with the same file name (iris.csv if
+    // there is none). Only a missing file moves on to the next source.
+    pub fn load_with_fallback(config: &DatasetConfig) -> Result<Dataset, DatasetError> {
+        if config.path_given {
+            return Dataset::load(config);
+        }
+
+        let not_found = |error: &DatasetError| matches!(&error.reason, DatasetErrorReason::Io(io) if io.kind() == std::io::ErrorKind::NotFound);
+
+        let mut paths: Vec<String> = std::env::var(PATH_VARIABLE)
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+            .into_iter()
+            .collect();
+        if !paths.contains(&config.path) {
+            paths.push(config.path.clone());
+        }
+
+        for path in paths {
+            let config = DatasetConfig {
+                path,
+                ..config.clone()
+            };
+
+            match Dataset::load(&config) {
+                Err(error) if not_found(&error) => continue,
+                result => return result,
+            }
+        }
+
+        let file_name = std::path::Path::new(&config.path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let (name, text) = EMBEDDED
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&file_name))
+            .unwrap_or(&EMBEDDED[0]);
+
+        let config = DatasetConfig {
+            path: format!("embedded {}", name),
+            ..config.clone()
+        };
+
+        Dataset::read(text.as_bytes(), &config)
+    }
+
+    pub fn read(reader: impl Read, config: &DatasetConfig) -> Result<Dataset, DatasetError> {
+        let error = |line: Option<u64>, column: Option<&String>, reason| DatasetError {
+            file: config.path.clone(),
+            line,
+            column: column.cloned(),
+            reason,
+        };
+
+        let mut skipped = vec![];
+        let mut records: Vec<csv::StringRecord> = vec![];
+
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+
+        for record in reader.records() {
+            match record {
+                Ok(record) => records.push(record),
+                Err(csv_error) => {
+                    let line = csv_error.position().map(|p| p.line());
+                    let csv_error = error(line, None, DatasetErrorReason::Csv(csv_error));
+
+                    if !config.tolerant {
+                        return Err(csv_error);
+                    }
+                    skipped.push(csv_error);
+                }
+            }
+        }
+
+        let has_header = config.has_header.unwrap_or_else(|| detect_header(&records));
+
+        Dataset::from_records(&records, has_header, skipped, config)
+    }
+
+    // Build the dataset from rows of text fields, shared by every file format
+    pub fn from_records(
+        records: &[csv::StringRecord],
+        has_header: bool,
+        mut skipped: Vec<DatasetError>,
+        config: &DatasetConfig,
+    ) -> Result<Dataset, DatasetError> {
+        let error = |line: Option<u64>, column: Option<&String>, reason| DatasetError {
+            file: config.path.clone(),
+            line,
+            column: column.cloned(),
+            reason,
+        };
+
+        let Some(first) = records.first() else {
+            return Err(error(None, None, DatasetErrorReason::EmptyFile));
+        };
+
+        let columns = first.len();
+
+        let names: Vec<String> = if has_header {
+            first.iter().map(|name| name.to_owned()).collect()
+        } else {
+            (0..columns).map(|i| format!("Column {}", i)).collect()
+        };
+
+        let find_column = |column: &String| {
+            column_index(column, &names).ok_or_else(|| {
+                error(
+                    None,
+                    None,
+                    DatasetErrorReason::ColumnNotFound(column.clone()),
+                )
+            })
+        };
+
+        // The columns written after the label by an export are skipped
+        let label = match &config.label_column {
+            Some(column) => find_column(column)?,
+            None => (0..columns)
+                .rev()
+                .find(|&i| !formats::is_prediction_column(&names[i]))
+                .unwrap_or(columns.saturating_sub(1)),
+        };
+
+        let features: Vec<usize> = if config.feature_columns.is_empty() {
+            (0..columns)
+                .filter(|&i| {
+                    i != label
+                        && !names[i].eq_ignore_ascii_case("id")
+                        && !formats::is_prediction_column(&names[i])
+                })
+                .collect()
+        } else {
+            config
+                .feature_columns
+                .iter()
+                .map(find_column)
+                .collect::<Result<_, _>>()?
+        };
+
+        if features.len() < 2 {
+            return Err(error(
+                None,
+                None,
+                DatasetErrorReason::NotEnoughFeatures(features.len()),
+            ));
+        }
+
+        let mut dataset = Dataset {
+            feature_names: features.iter().map(|&i| names[i].clone()).collect(),
+            class_names: vec![],
+            samples: vec![],
+            skipped: vec![],
+            source: config.path.clone(),
+        };
+
+        for record in records.iter().skip(has_header as usize) {
+            let line = record.position().map(|p| p.line());
+
+            let row = Dataset::read_row(record, label, &features)
+                .map_err(|(column, reason)| error(line, column.map(|i| &names[i]), reason));
+
+            let (raw_label, features) = match row {
+                Ok(row) => row,
+                Err(row_error) if config.tolerant => {
+                    skipped.push(row_error);
+                    continue;
+                }
+                Err(row_error) => return Err(row_error),
+            };
+
+            let class_name = config
+                .label_map
+                .iter()
+                .find(|(raw, _)| raw == raw_label)
+                .map(|(_, name)| name.as_str())
+                .unwrap_or(raw_label);
+
+            let class = match dataset.class_names.iter().position(|c| c == class_name) {
+                Some(class) => class,
+                None => {
+                    dataset.class_names.push(class_name.to_owned());
+                    dataset.class_names.len() - 1
+                }
+            };
+
+            dataset.samples.push(Sample {
+                features,
+                class,
+                row: Some(dataset.samples.len()),
+            });
+        }
+
+        if dataset.class_names.len() < 2 {
+            return Err(error(
+                None,
+                None,
+                DatasetErrorReason::NotEnoughClasses(dataset.class_names.len()),
+            ));
+        }
+
+        dataset.skipped = skipped;
+
+        Ok(dataset)
+    }
+
+    // Returns the raw label and the features of a row, or the index of the bad column
+    fn read_row<'a>(
+        record: &'a csv::StringRecord,
+        label: usize,
+        features: &[usize],
+    ) -> Result<(&'a str, Vec<f64>), RowError> {
+        let raw_label = match record.get(label) {
+            Some(raw_label) if !raw_label.is_empty() => raw_label,
+            _ => return Err((Some(label), DatasetErrorReason::MissingField)),
+        };
+
+        let mut values = vec![];
+
+        for &i in features {
+            let field = record
+                .get(i)
+                .filter(|field| !field.is_empty())
+                .ok_or((Some(i), DatasetErrorReason::MissingField))?;
+
+            let value = field
+                .parse::<f64>()
+                .map_err(|_| (Some(i), DatasetErrorReason::InvalidNumber(field.to_owned())))?;
+
+            values.push(value);
+        }
+
+        Ok((raw_label, values))
+    }
+
+    // Samples of the positive class and of the negative one. No negative class means one-vs-rest.
+    pub fn get_data(&self, positive: usize, negative: Option<usize>) -> Vec<Sample> {
+        self.samples
+            .iter()
+            .filter(|s| negative.is_none() || s.class == positive || Some(s.class) == negative)
+            .cloned()
+            .collect()
+    }
+
+    // e.g. "Iris Virginica vs Iris Setosa" or "Iris Virginica vs rest"
+    pub fn pair_name(&self, positive: usize, negative: Option<usize>) -> String {
+        format!(
+            "{} vs {}",
+            self.class_names[positive],
+            negative
+                .map(|n| self.class_names[n].as_str())
+                .unwrap_or("rest")
+        )
+    }
+
+    // Indexes of the features used by the selected data type (0 = Sepal, 1 = Petal, 2 = All)
+    pub fn selected_features(&self, selected: i32) -> Vec<usize> {
+        let count = self.feature_names.len();
+
+        match selected {
+            0 => vec![0, 1],
+            1 if count >= 4 => vec![2, 3],
+            1 => vec![0, 1],
+            _ => (0..count).collect(),
+        }
+    }
+
+    // Build the (inputs, target) pairs, where the positive class is the target 1 and every other class the target 0
+    pub fn get_train_data(
+        data: &[Sample],
+        features: &[usize],
+        positive: usize,
+    ) -> Vec<(Vec<f64>, f64)> {
+        data.iter()
+            .map(|s| {
+                let inputs = features.iter().map(|&i| s.features[i]).collect();
+                let target = if s.class == positive { 1.0 } else { 0.0 };
+                (inputs, target)
+            })
+            .collect()
+    }
+
+    // The subset of each sample is shown by its marker and a corrupted sample gets an outline
+    pub fn get_graph_data(
+        data: &[Sample],
+        subsets: &[Subset],
+        corruptions: &[Corruption],
+        x: usize,
+        y: usize,
+    ) -> Vec<GraphPoint> {
+        data.iter()
+            .zip(subsets)
+            .zip(corruptions)
+            .enumerate()
+            .map(|(index, ((s, subset), corruption))| GraphPoint {
+                x: s.features[x] as f32,
+                y: s.features[y] as f32,
+                color: class_color(s.class),
+                marker: match subset {
+                    Subset::Train => Marker::Circle,
+                    Subset::Validation => Marker::Square,
+                    Subset::Test => Marker::Triangle,
+                },
+                outline: match corruption {
+                    Corruption::None => None,
+                    Corruption::Noise => Some(Color::LIGHTGRAY),
+                    Corruption::Flip => Some(Color::BLACK),
+                    Corruption::Outlier => Some(Color::MAGENTA),
+                },
+                index,
+            })
+            .collect()
+    }
+
+    // The samples at the given indexes
+    pub fn select(data: &[Sample], indexes: &[usize]) -> Vec<Sample> {
+        indexes.iter().map(|&i| data[i].clone()).collect()
+    }
+
+    pub fn get_test_data(data: &[Sample]) -> Vec<(Vec<f64>, usize)> {
+        data.iter().map(|s| (s.features.clone(), s.class)).collect()
+    }
+}
+
+pub fn class_color(class: usize) -> Color {
+    const COLORS: [Color; 6] = [
+        Color::RED,
+        Color::BLUE,
+        Color::GREEN,
+        Color::ORANGE,
+        Color::PURPLE,
+        Color::BROWN,
+    ];
+
+    COLORS[class % COLORS.len()]
+}
+
+// Short column name for tables, e.g. "sepal_length" -> "SL" and "SepalLengthCm" -> "SLC"
+pub fn short_name(name: &str) -> String {
+    let uppercase: String = name.chars().filter(|c| c.is_ascii_uppercase()).collect();
+
+    if uppercase.len() >= 2 {
+        return uppercase;
+    }
+
+    name.split(['_', ' ', '-'])
+        .filter_map(|word| word.chars().next())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+// Short names that can be told apart, e.g. "Iris Versicolor" and "Iris Virginica" both shorten to
+// "IV", so they get the first letters of their last word that differ: "Ver" and "Vir"
+pub fn unique_short_names(names: &[String]) -> Vec<String> {
+    let short: Vec<String> = names.iter().map(|name| short_name(name)).collect();
+    let last_word = |name: &str| -> String {
+        name.split(['_', ' ', '-'])
+            .next_back()
+            .unwrap_or(name)
+            .to_owned()
+    };
+
+    names
+        .iter()
+        .zip(short.iter())
+        .map(|(name, abbreviation)| {
+            let others: Vec<String> = names
+                .iter()
+                .zip(short.iter())
+                .filter(|(other, a)| *a == abbreviation && *other != name)
+                .map(|(other, _)| last_word(other))
+                .collect();
+
+            if others.is_empty() {
+                return abbreviation.clone();
+            }
+
+            let word = last_word(name);
+            let chars: Vec<char> = word.chars().collect();
+
+            (3..=chars.len())
+                .map(|n| chars[..n].iter().collect::<String>())
+                .find(|prefix| {
+                    others
+                        .iter()
+                        .all(|other| !other.starts_with(prefix.as_str()))
+                })
+                .unwrap_or(word)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(text: &str, config: &DatasetConfig) -> Result<Dataset, DatasetError> {
+        Dataset::read(text.as_bytes(), config)
+    }
+
+    #[test]
+    fn header_is_detected() {
+        let config = DatasetConfig::default();
+
+        let with_header = read("width,height,kind\n1,2,a\n3,4,b\n", &config).unwrap();
+        assert_eq!(with_header.feature_names, ["width", "height"]);
+        assert_eq!(with_header.samples.len(), 2);
+
+        let without_header = read("1,2,a\n3,4,b\n", &config).unwrap();
+        assert_eq!(without_header.feature_names, ["Column 0", "Column 1"]);
+        assert_eq!(without_header.samples.len(), 2);
+    }
+
+    #[test]
+    fn default_columns_skip_id_and_use_last_label() {
+        let dataset = read(EMBEDDED[1].1, &DatasetConfig::default()).unwrap();
+
+        assert_eq!(
+            dataset.feature_names,
+            [
+                "SepalLengthCm",
+                "SepalWidthCm",
+                "PetalLengthCm",
+                "PetalWidthCm"
+            ]
+        );
+        assert_eq!(dataset.class_names, ["Iris Setosa", "Iris Virginica"]);
+        assert_eq!(dataset.samples.len(), 100);
+        assert_eq!(dataset.samples[0].features, [5.1, 3.5, 1.4, 0.2]);
+    }
+
+    #[test]
+    fn label_map_renames_classes() {
+        let config = DatasetConfig {
+            label_map: vec![("a".to_owned(), "First".to_owned())],
+            ..DatasetConfig::default()
+        };
+        let dataset = read("x,y,label\n1,2,a\n3,4,b\n5,6,a\n", &config).unwrap();
+
+        assert_eq!(dataset.class_names, ["First", "b"]);
+        let classes: Vec<usize> = dataset.samples.iter().map(|s| s.class).collect();
+        assert_eq!(classes, [0, 1, 0]);
+    }
+
+    #[test]
+    fn columns_by_name_or_index() {
+        let config = DatasetConfig {
+            feature_columns: vec!["z".to_owned(), "0".to_owned()],
+            label_column: Some("label".to_owned()),
+            ..DatasetConfig::default()
+        };
+        let dataset = read("x,label,z\n1,a,2\n3,b,4\n", &config).unwrap();
+
+        assert_eq!(dataset.feature_names, ["z", "x"]);
+        assert_eq!(dataset.samples[1].features, [4.0, 3.0]);
+
+        let missing = DatasetConfig {
+            label_column: Some("species".to_owned()),
+            ..DatasetConfig::default()
+        };
+        assert!(matches!(
+            read("x,label,z\n1,a,2\n", &missing).unwrap_err().reason,
+            DatasetErrorReason::ColumnNotFound(_)
+        ));
+    }
+
+    #[test]
+    fn missing_default_path_falls_back_to_embedded_copy() {
+        // The variable would be tried before the default path
+        std::env::remove_var(PATH_VARIABLE);
+
+        let directory = std::env::temp_dir().join(format!("missing-{}", std::process::id()));
+
+        for (file_name, classes) in [("iris.csv", 3), ("Iris-old.csv", 2)] {
+            let config = DatasetConfig {
+                path: directory.join(file_name).to_string_lossy().to_string(),
+                ..DatasetConfig::default()
+            };
+            let dataset = Dataset::load_with_fallback(&config).unwrap();
+
+            assert_eq!(dataset.source, format!("embedded {}", file_name));
+            assert_eq!(dataset.class_names.len(), classes);
+        }
+    }
+
+    #[test]
+    fn bad_rows_report_line_and_column() {
+        let text = "x,y,label\n1,2,a\n1,oops,b\n3,,a\n4,5,b\n";
+
+        let error = read(text, &DatasetConfig::default()).unwrap_err();
+        assert_eq!(error.line, Some(3));
+        assert_eq!(error.column.as_deref(), Some("y"));
+        assert!(matches!(
+            error.reason,
+            DatasetErrorReason::InvalidNumber(ref value) if value == "oops"
+        ));
+
+        let tolerant = DatasetConfig {
+            tolerant: true,
+            ..DatasetConfig::default()
+        };
+        let dataset = read(text, &tolerant).unwrap();
+
+        assert_eq!(dataset.samples.len(), 2);
+        assert_eq!(dataset.skipped.len(), 2);
+        assert_eq!(dataset.skipped[1].line, Some(4));
+        assert_eq!(dataset.skipped[1].column.as_deref(), Some("y"));
+        assert!(matches!(
+            dataset.skipped[1].reason,
+            DatasetErrorReason::MissingField
+        ));
+    }
+}
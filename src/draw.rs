@@ -1,7 +1,13 @@
 use crate::{
-    components::{data_values, draw_table, frequency, iris_data_type_box, iris_type_box},
-    state::{update_data, update_data_type, update_iris_type},
-    State, WINDOW_WIDTH,
+    components::{
+        calibration_panel, cross_validation_panel, data_values, dataset_panel, draw_table,
+        feature_boxes, frequency, graph_editor, graph_option_boxes, graph_view_controls,
+        imbalance_panel, iris_data_type_box, iris_type_box, model_buttons, negative_class_box,
+        pair_plot_panel, panel_box, parallel_coordinates_panel, perturbation_panel,
+        point_selection, point_tooltip, projection_box, scaling_box, statistics_panel,
+    },
+    state::{brushed_samples, update_class_pair, update_data, update_data_type},
+    State, WINDOW_HEIGHT, WINDOW_WIDTH,
 };
 use raylib::prelude::*;
 
@@ -11,6 +17,8 @@ pub fn update(d: &mut RaylibDrawHandle, state: &mut State) {
     d.draw_fps(0, 0);
 
     // Separation graph
+    state.separation_graph.selection = brushed_samples(state);
+    state.separation_graph.pinned = state.selected_sample;
     state.separation_graph.draw(d);
 
     // Line graph
@@ -26,29 +34,83 @@ pub fn update(d: &mut RaylibDrawHandle, state: &mut State) {
 
     data_values(d, state);
 
-    draw_table(d, state, Rectangle::new(400.0, 420.0, 300.0, 300.0));
+    // Status bar
+    d.draw_text(
+        &format!("Dataset: {}", state.csv_data.source),
+        580,
+        WINDOW_HEIGHT as i32 - 40,
+        15,
+        Color::DARKGRAY,
+    );
+
+    if !state.csv_data.skipped.is_empty() {
+        d.draw_text(
+            &format!("{} bad rows skipped", state.csv_data.skipped.len()),
+            580,
+            WINDOW_HEIGHT as i32 - 20,
+            15,
+            Color::ORANGE,
+        );
+    }
+
+    match state.panel_select.1 {
+        1 => cross_validation_panel(d, state, Rectangle::new(400.0, 420.0, 300.0, 300.0)),
+        2 => calibration_panel(d, state, Rectangle::new(400.0, 420.0, 300.0, 300.0)),
+        3 => dataset_panel(d, state, Rectangle::new(400.0, 420.0, 300.0, 300.0)),
+        4 => statistics_panel(d, state, Rectangle::new(400.0, 420.0, 300.0, 300.0)),
+        5 => perturbation_panel(d, state, Rectangle::new(400.0, 420.0, 300.0, 300.0)),
+        6 => imbalance_panel(d, state, Rectangle::new(400.0, 420.0, 300.0, 300.0)),
+        7 => pair_plot_panel(d, state, Rectangle::new(400.0, 420.0, 300.0, 300.0)),
+        8 => parallel_coordinates_panel(d, state, Rectangle::new(400.0, 420.0, 300.0, 300.0)),
+        _ => draw_table(d, state, Rectangle::new(400.0, 420.0, 300.0, 300.0)),
+    }
 
     // Inputs
     if d.is_key_pressed(KeyboardKey::KEY_P) {
         state.pause = !state.pause;
     }
 
+    graph_view_controls(d, state, Rectangle::new(40.0, 702.0, 0.0, 16.0));
+    point_selection(d, state);
+    graph_editor(d, state);
+
     let data_type_selected = iris_data_type_box(d, state, Rectangle::new(120.0, 0.0, 80.0, 30.0));
     frequency(d, state, Rectangle::new(480.0, 680.0, 80.0, 30.0));
 
-    let iris_type_selected = iris_type_box(d, state, Rectangle::new(210.0, 0.0, 100.0, 30.0));
+    let positive_selected = iris_type_box(d, state, Rectangle::new(210.0, 0.0, 120.0, 30.0));
+
+    d.draw_text("vs", 335, 8, 15, Color::BLACK);
+
+    let negative_selected = negative_class_box(d, state, Rectangle::new(355.0, 0.0, 120.0, 30.0));
+
+    panel_box(d, state, Rectangle::new(485.0, 0.0, 120.0, 30.0));
+
+    let scaling_selected = scaling_box(d, state, Rectangle::new(615.0, 0.0, 90.0, 30.0));
+
+    model_buttons(d, state, Rectangle::new(715.0, 0.0, 120.0, 30.0));
+
+    projection_box(d, state, Rectangle::new(720.0, 510.0, 70.0, 0.0));
+    graph_option_boxes(d, state, Rectangle::new(720.0, 655.0, 0.0, 0.0));
+
+    let features_selected = feature_boxes(d, state, Rectangle::new(720.0, 400.0, 150.0, 0.0));
 
     // Neuron
     state.neuron.draw(d, 700, 190, 60.0, Some(state.outputs.0));
 
+    // Over everything else
+    point_tooltip(d, state);
+
     // Type change
-    if let Some(iris_type) = iris_type_selected {
-        update_iris_type(state, iris_type);
+    if positive_selected || negative_selected {
+        update_class_pair(state);
         update_data_type(state, state.iris_select.1);
     }
-    if let Some(selected) = data_type_selected {
+    if let Some(selected) = data_type_selected.or(features_selected) {
         update_data_type(state, selected);
     }
+    if scaling_selected.is_some() {
+        update_data_type(state, state.iris_select.1);
+    }
 
     // Update data
     if state.pause {
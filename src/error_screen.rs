@@ -0,0 +1,208 @@
+use std::{ffi::CString, path::Path};
+
+use raylib::prelude::*;
+
+use crate::{
+    dataset::{Dataset, DatasetConfig, DatasetError},
+    WINDOW_WIDTH,
+};
+
+pub const PATH_SIZE: usize = 256;
+const FONT_SIZE: i32 = 20;
+
+// Shown instead of the demo when the dataset can't be loaded, so another file can be picked
+pub struct ErrorScreen {
+    pub error: DatasetError,
+    pub files: Vec<String>,
+    pub list_scroll: i32,
+    pub list_active: i32,
+    pub path: [u8; PATH_SIZE],
+    pub path_edit: bool,
+}
+
+// Dataset files next to the one that failed and in the working directory
+fn list_files(path: &str) -> Vec<String> {
+    let parent = Path::new(path)
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let mut files = vec![];
+
+    for dir in [parent, Path::new(".")] {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let file = entry.path();
+            let is_dataset = file.extension().is_some_and(|e| {
+                ["csv", "arff", "json", "jsonl", "ndjson"]
+                    .iter()
+                    .any(|ext| e.eq_ignore_ascii_case(ext))
+            });
+
+            if is_dataset {
+                let file = file.to_string_lossy().to_string();
+                if !files.contains(&file) {
+                    files.push(file);
+                }
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+// A text box buffer holding the path, cut to fit
+pub fn path_buffer(path: &str) -> [u8; PATH_SIZE] {
+    let mut buffer = [0; PATH_SIZE];
+    let len = path.len().min(PATH_SIZE - 1);
+    buffer[..len].copy_from_slice(&path.as_bytes()[..len]);
+    buffer
+}
+
+// The path typed in a text box buffer
+pub fn typed_path(buffer: &[u8; PATH_SIZE]) -> String {
+    let len = buffer.iter().position(|&b| b == 0).unwrap_or(PATH_SIZE);
+    String::from_utf8_lossy(&buffer[..len]).trim().to_owned()
+}
+
+impl ErrorScreen {
+    pub fn new(error: DatasetError, path: &str) -> Self {
+        Self {
+            error,
+            files: list_files(path),
+            list_scroll: 0,
+            list_active: -1,
+            path: path_buffer(path),
+            path_edit: false,
+        }
+    }
+
+    // Returns the path picked by the user, if any
+    pub fn draw(&mut self, d: &mut RaylibDrawHandle, config: &mut DatasetConfig) -> Option<String> {
+        d.clear_background(Color::WHITE);
+
+        let x = 40;
+        let mut y = 40;
+
+        d.draw_text("Could not load the dataset", x, y, 30, Color::RED);
+        y += 40;
+
+        // Long messages are wrapped so they stay inside the window
+        let message = self.error.to_string();
+        let max_chars = ((WINDOW_WIDTH as i32 - 2 * x) / d.measure_text("m", FONT_SIZE)) as usize;
+
+        for chunk in message
+            .chars()
+            .collect::<Vec<char>>()
+            .chunks(max_chars.max(1))
+        {
+            d.draw_text(
+                &chunk.iter().collect::<String>(),
+                x,
+                y,
+                FONT_SIZE,
+                Color::BLACK,
+            );
+            y += FONT_SIZE + 2;
+        }
+
+        y += 20;
+
+        d.draw_text(
+            "Pick a dataset file, type its path or drop it on the window",
+            x,
+            y,
+            FONT_SIZE,
+            Color::DARKGRAY,
+        );
+        y += FONT_SIZE + 10;
+
+        let mut chosen = None;
+
+        if d.is_file_dropped() {
+            let dropped = d.load_dropped_files();
+            chosen = dropped.paths().first().map(|p| p.to_string());
+        }
+
+        if d.gui_text_box(
+            Rectangle::new(x as f32, y as f32, 600.0, 30.0),
+            &mut self.path,
+            self.path_edit,
+        ) {
+            self.path_edit = !self.path_edit;
+        }
+
+        if d.gui_button(
+            Rectangle::new(x as f32 + 610.0, y as f32, 80.0, 30.0),
+            Some(rstr!("Load")),
+        ) {
+            chosen = Some(typed_path(&self.path));
+        }
+
+        y += 40;
+
+        d.gui_check_box(
+            Rectangle::new(x as f32, y as f32, 20.0, 20.0),
+            Some(rstr!("Skip bad rows")),
+            &mut config.tolerant,
+        );
+
+        y += 30;
+
+        if self.files.is_empty() {
+            d.draw_text("No dataset files found", x, y, FONT_SIZE, Color::GRAY);
+        } else {
+            let last_active = self.list_active;
+            let options = CString::new(self.files.join(";")).unwrap();
+
+            d.gui_list_view(
+                Rectangle::new(x as f32, y as f32, 690.0, 300.0),
+                Some(options.as_c_str()),
+                &mut self.list_scroll,
+                &mut self.list_active,
+            );
+
+            if self.list_active != last_active && self.list_active >= 0 {
+                chosen = self.files.get(self.list_active as usize).cloned();
+            }
+        }
+
+        chosen
+    }
+}
+
+// Keep showing the error screen until a dataset loads. Returns None if the window is closed.
+pub fn run(
+    rl: &mut RaylibHandle,
+    thread: &RaylibThread,
+    config: &mut DatasetConfig,
+    error: DatasetError,
+) -> Option<Dataset> {
+    let mut screen = ErrorScreen::new(error, &config.path);
+
+    while !rl.window_should_close() {
+        let chosen = {
+            let mut d = rl.begin_drawing(thread);
+            screen.draw(&mut d, config)
+        };
+
+        if let Some(path) = chosen {
+            config.path = path;
+            config.path_given = true;
+
+            match Dataset::load(config) {
+                Ok(dataset) => return Some(dataset),
+                Err(error) => {
+                    screen.error = error;
+                    screen.list_active = -1;
+                }
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,667 @@
+// Weka ARFF and JSON datasets. Both are turned into text records and go through the same
+// column and label handling as the CSV files.
+use std::{
+    fmt::Write as _,
+    io::{BufRead, BufReader, Read},
+};
+
+use csv::{Position, StringRecord};
+use serde_json::{Map, Value};
+
+use crate::{
+    dataset::{Dataset, DatasetConfig, DatasetError, DatasetErrorReason, Sample},
+    split::Subset,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Csv,
+    Arff,
+    // A JSON array of objects, or one object per line
+    Json,
+}
+
+impl Format {
+    pub fn from_path(path: &str) -> Format {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "arff" => Format::Arff,
+            "json" | "jsonl" | "ndjson" => Format::Json,
+            _ => Format::Csv,
+        }
+    }
+}
+
+fn format_error(config: &DatasetConfig, line: Option<u64>, message: String) -> DatasetError {
+    DatasetError {
+        file: config.path.clone(),
+        line,
+        column: None,
+        reason: DatasetErrorReason::Format(message),
+    }
+}
+
+fn record_at(fields: Vec<String>, line: Option<u64>) -> StringRecord {
+    let mut record = StringRecord::from(fields);
+
+    if let Some(line) = line {
+        let mut position = Position::new();
+        position.set_line(line);
+        record.set_position(Some(position));
+    }
+
+    record
+}
+
+// Inside quotes, a backslash escapes the next character
+fn unescape(value: &str) -> String {
+    let mut text = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.extend(chars.next()),
+            c => text.push(c),
+        }
+    }
+
+    text
+}
+
+// Position of the quote that closes a value starting after an opening quote
+fn closing_quote(value: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == quote => return Some(i),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+// ARFF values can be quoted with ' or "
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+
+    for quote in ['\'', '"'] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return unescape(&value[1..value.len() - 1]);
+        }
+    }
+
+    value.to_owned()
+}
+
+// Split on commas that aren't inside quotes
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quote = None;
+    let mut escaped = false;
+
+    for c in line.chars() {
+        match (c, quote) {
+            (_, Some(_)) if escaped => escaped = false,
+            ('\\', Some(_)) => escaped = true,
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            (',', None) => {
+                fields.push(unquote(&field));
+                field.clear();
+                continue;
+            }
+            _ => {}
+        }
+        field.push(c);
+    }
+
+    fields.push(unquote(&field));
+    fields
+}
+
+struct Attribute {
+    name: String,
+    // The declared values of a nominal attribute, None for a numeric one
+    values: Option<Vec<String>>,
+}
+
+// "@attribute name type", where the name may be quoted and the type may be {a,b,c}
+fn parse_attribute(line: &str) -> Option<Attribute> {
+    let rest = line.trim()["@attribute".len()..].trim();
+
+    let (name, kind) = match rest.chars().next()? {
+        quote @ ('\'' | '"') => {
+            let end = closing_quote(&rest[1..], quote)? + 1;
+            (unescape(&rest[1..end]), rest[end + 1..].trim())
+        }
+        _ => {
+            let end = rest.find(char::is_whitespace)?;
+            (rest[..end].to_owned(), rest[end..].trim())
+        }
+    };
+
+    let values = if kind.starts_with('{') {
+        let inner = kind.trim_start_matches('{').trim_end_matches('}');
+        Some(split_fields(inner))
+    } else {
+        None
+    };
+
+    Some(Attribute { name, values })
+}
+
+pub fn read_arff(reader: impl Read, config: &DatasetConfig) -> Result<Dataset, DatasetError> {
+    let mut attributes: Vec<Attribute> = vec![];
+    let mut records = vec![];
+    let mut in_data = false;
+
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line_number = Some(i as u64 + 1);
+
+        let line = line.map_err(|error| DatasetError {
+            file: config.path.clone(),
+            line: line_number,
+            column: None,
+            reason: DatasetErrorReason::Io(error),
+        })?;
+
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('%') {
+            continue;
+        }
+
+        let keyword = line.to_lowercase();
+
+        if in_data {
+            // Missing values are written as ?
+            let fields = split_fields(line)
+                .into_iter()
+                .map(|f| if f == "?" { String::new() } else { f })
+                .collect();
+            records.push(record_at(fields, line_number));
+        } else if keyword.starts_with("@attribute") {
+            let attribute = parse_attribute(line).ok_or_else(|| {
+                format_error(config, line_number, format!("invalid attribute '{}'", line))
+            })?;
+            attributes.push(attribute);
+        } else if keyword.starts_with("@data") {
+            in_data = true;
+        } else if !keyword.starts_with("@relation") {
+            return Err(format_error(
+                config,
+                line_number,
+                format!("unexpected line '{}'", line),
+            ));
+        }
+    }
+
+    if !in_data {
+        return Err(format_error(config, None, "no @data section".to_owned()));
+    }
+
+    // The class is the last nominal attribute unless a label column is given
+    let mut config = config.clone();
+
+    if config.label_column.is_none() {
+        config.label_column = attributes
+            .iter()
+            .rev()
+            .find(|a| a.values.is_some())
+            .map(|a| a.name.clone());
+    }
+
+    // Only the numeric attributes can be features, the outputs of an export aren't
+    if config.feature_columns.is_empty() {
+        config.feature_columns = attributes
+            .iter()
+            .filter(|a| {
+                a.values.is_none()
+                    && !a.name.eq_ignore_ascii_case("id")
+                    && !is_prediction_column(&a.name)
+            })
+            .map(|a| a.name.clone())
+            .collect();
+    }
+
+    // Labels have to be one of the declared values
+    if let Some(label) = attributes.iter().position(|a| {
+        config
+            .label_column
+            .as_ref()
+            .is_some_and(|l| a.name.eq_ignore_ascii_case(l))
+    }) {
+        if let Some(values) = &attributes[label].values {
+            for record in records.iter() {
+                let value = record.get(label).unwrap_or("");
+
+                if !value.is_empty() && !values.iter().any(|v| v == value) {
+                    return Err(DatasetError {
+                        file: config.path.clone(),
+                        line: record.position().map(|p| p.line()),
+                        column: Some(attributes[label].name.clone()),
+                        reason: DatasetErrorReason::UnknownLabel(value.to_owned()),
+                    });
+                }
+            }
+        }
+    }
+
+    let header = record_at(attributes.iter().map(|a| a.name.clone()).collect(), None);
+    records.insert(0, header);
+
+    Dataset::from_records(&records, true, vec![], &config)
+}
+
+fn field_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+pub fn read_json(mut reader: impl Read, config: &DatasetConfig) -> Result<Dataset, DatasetError> {
+    let mut text = String::new();
+
+    reader
+        .read_to_string(&mut text)
+        .map_err(|error| DatasetError {
+            file: config.path.clone(),
+            line: None,
+            column: None,
+            reason: DatasetErrorReason::Io(error),
+        })?;
+
+    // A JSON array, or JSON lines with one object on each line
+    let objects: Vec<(Option<u64>, Map<String, Value>)> = if text.trim_start().starts_with('[') {
+        serde_json::from_str::<Vec<Map<String, Value>>>(&text)
+            .map_err(|error| format_error(config, Some(error.line() as u64), error.to_string()))?
+            .into_iter()
+            .map(|object| (None, object))
+            .collect()
+    } else {
+        text.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                let line_number = Some(i as u64 + 1);
+                serde_json::from_str(line)
+                    .map(|object| (line_number, object))
+                    .map_err(|error| format_error(config, line_number, error.to_string()))
+            })
+            .collect::<Result<_, _>>()?
+    };
+
+    let Some((_, first)) = objects.first() else {
+        return Err(format_error(config, None, "no objects".to_owned()));
+    };
+
+    let names: Vec<String> = first.keys().cloned().collect();
+
+    // The class is the text field unless a label column is given
+    let mut config = config.clone();
+
+    if config.label_column.is_none() {
+        config.label_column = first
+            .iter()
+            .find(|(_, value)| value.is_string())
+            .map(|(name, _)| name.clone());
+    }
+
+    // Only the number fields can be features, the outputs of an export aren't
+    if config.feature_columns.is_empty() {
+        config.feature_columns = first
+            .iter()
+            .filter(|(name, value)| {
+                value.is_number() && !name.eq_ignore_ascii_case("id") && !is_prediction_column(name)
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+    }
+
+    let mut records = vec![record_at(names.clone(), None)];
+
+    for (line, object) in objects.iter() {
+        let fields = names
+            .iter()
+            .map(|name| object.get(name).map(field_text).unwrap_or_default())
+            .collect();
+        records.push(record_at(fields, *line));
+    }
+
+    Dataset::from_records(&records, true, vec![], &config)
+}
+
+// Columns added to the features by an export. They hold outputs of the neuron, so they aren't read
+// back as features.
+pub const PREDICTION_COLUMNS: [&str; 4] = ["split", "probability", "prediction", "loss"];
+
+pub fn is_prediction_column(name: &str) -> bool {
+    PREDICTION_COLUMNS
+        .iter()
+        .any(|column| name.eq_ignore_ascii_case(column))
+}
+
+// A sample with the prediction of the neuron, as exported
+pub struct PredictionRow {
+    pub features: Vec<f64>,
+    pub class: String,
+    // None for the samples outside the selected classes
+    pub subset: Option<Subset>,
+    pub probability: f64,
+    pub prediction: String,
+    // Log loss against the target, only known for the samples of the selected classes
+    pub loss: Option<f64>,
+}
+
+// Log loss of a probability against the target of a sample, only known for the samples of the
+// selected classes
+pub fn sample_loss(
+    sample: &Sample,
+    probability: f64,
+    positive: usize,
+    negative: Option<usize>,
+) -> Option<f64> {
+    let in_pair = negative.is_none() || sample.class == positive || Some(sample.class) == negative;
+    let target = if sample.class == positive { 1.0 } else { 0.0 };
+    let clamped = probability.clamp(1e-12, 1.0 - 1e-12);
+
+    in_pair.then(|| -(target * clamped.ln() + (1.0 - target) * (1.0 - clamped).ln()))
+}
+
+// The samples of the selected classes with their subset, then every other sample of the dataset.
+// With a subset, only its samples are kept.
+pub fn prediction_rows(
+    dataset: &Dataset,
+    data: &[Sample],
+    subsets: &[Subset],
+    positive: usize,
+    negative: Option<usize>,
+    only: Option<Subset>,
+    mut probability: impl FnMut(&Sample) -> f64,
+) -> Vec<PredictionRow> {
+    let negative_name = match negative {
+        Some(negative) => dataset.class_names[negative].clone(),
+        None => "rest".to_owned(),
+    };
+
+    let mut row = |sample: &Sample, subset: Option<Subset>| {
+        let p = probability(sample);
+
+        PredictionRow {
+            features: sample.features.clone(),
+            class: dataset.class_names[sample.class].clone(),
+            subset,
+            probability: p,
+            prediction: if p >= 0.5 {
+                dataset.class_names[positive].clone()
+            } else {
+                negative_name.clone()
+            },
+            loss: sample_loss(sample, p, positive, negative),
+        }
+    };
+
+    let mut rows: Vec<PredictionRow> = data
+        .iter()
+        .zip(subsets)
+        .filter(|(_, subset)| only.is_none_or(|only| only == **subset))
+        .map(|(sample, subset)| row(sample, Some(*subset)))
+        .collect();
+
+    if only.is_none() && negative.is_some() {
+        rows.extend(
+            dataset
+                .samples
+                .iter()
+                .filter(|s| s.class != positive && Some(s.class) != negative)
+                .map(|sample| row(sample, None)),
+        );
+    }
+
+    rows
+}
+
+// The format is picked from the extension of the path
+pub fn write_predictions(
+    path: &str,
+    relation: &str,
+    feature_names: &[String],
+    class_names: &[String],
+    rows: &[PredictionRow],
+) -> Result<(), String> {
+    match Format::from_path(path) {
+        Format::Csv => write_csv(path, feature_names, rows),
+        Format::Arff => write_arff(path, relation, feature_names, class_names, rows),
+        Format::Json => write_json(path, feature_names, rows, !path.ends_with(".json")),
+    }
+}
+
+pub fn write_csv(
+    path: &str,
+    feature_names: &[String],
+    rows: &[PredictionRow],
+) -> Result<(), String> {
+    let error = |error: csv::Error| format!("{}: {}", path, error);
+
+    let mut writer = csv::Writer::from_path(path).map_err(error)?;
+
+    let mut header = feature_names.to_vec();
+    header.extend(
+        ["species", "split", "probability", "prediction", "loss"]
+            .iter()
+            .map(|c| c.to_string()),
+    );
+    writer.write_record(&header).map_err(error)?;
+
+    for row in rows {
+        let mut record: Vec<String> = row.features.iter().map(|v| v.to_string()).collect();
+        record.push(row.class.clone());
+        record.push(row.subset.map(|s| s.name()).unwrap_or("").to_owned());
+        record.push(format!("{:.6}", row.probability));
+        record.push(row.prediction.clone());
+        record.push(row.loss.map(|l| format!("{:.6}", l)).unwrap_or_default());
+
+        writer.write_record(&record).map_err(error)?;
+    }
+
+    writer.flush().map_err(|e| format!("{}: {}", path, e))
+}
+
+pub fn write_arff(
+    path: &str,
+    relation: &str,
+    feature_names: &[String],
+    class_names: &[String],
+    rows: &[PredictionRow],
+) -> Result<(), String> {
+    let quote = |value: &str| format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"));
+    let nominal = |values: &[String]| {
+        values
+            .iter()
+            .map(|v| quote(v))
+            .collect::<Vec<String>>()
+            .join(",")
+    };
+
+    let classes = nominal(class_names);
+
+    // A one-vs-rest prediction can be "rest"
+    let mut predictions = class_names.to_vec();
+    for row in rows {
+        if !predictions.contains(&row.prediction) {
+            predictions.push(row.prediction.clone());
+        }
+    }
+    let predictions = nominal(&predictions);
+
+    let mut text = String::new();
+
+    writeln!(text, "@relation {}", quote(relation)).unwrap();
+    writeln!(text).unwrap();
+
+    for name in feature_names {
+        writeln!(text, "@attribute {} numeric", quote(name)).unwrap();
+    }
+
+    // The class is the last nominal attribute, so the file reads back with the true class
+    writeln!(text, "@attribute split {{train,validation,test}}").unwrap();
+    writeln!(text, "@attribute probability numeric").unwrap();
+    writeln!(text, "@attribute loss numeric").unwrap();
+    writeln!(text, "@attribute prediction {{{}}}", predictions).unwrap();
+    writeln!(text, "@attribute class {{{}}}", classes).unwrap();
+    writeln!(text).unwrap();
+    writeln!(text, "@data").unwrap();
+
+    for row in rows {
+        let mut fields: Vec<String> = row.features.iter().map(|v| v.to_string()).collect();
+        // ? is a missing value
+        fields.push(row.subset.map(|s| s.name()).unwrap_or("?").to_owned());
+        fields.push(format!("{:.6}", row.probability));
+        fields.push(
+            row.loss
+                .map(|l| format!("{:.6}", l))
+                .unwrap_or("?".to_owned()),
+        );
+        fields.push(quote(&row.prediction));
+        fields.push(quote(&row.class));
+
+        writeln!(text, "{}", fields.join(",")).unwrap();
+    }
+
+    std::fs::write(path, text).map_err(|error| format!("{}: {}", path, error))
+}
+
+// JSON lines when `lines` is set, a JSON array otherwise. The class comes before the
+// prediction, so the file reads back with the true class.
+pub fn write_json(
+    path: &str,
+    feature_names: &[String],
+    rows: &[PredictionRow],
+    lines: bool,
+) -> Result<(), String> {
+    let objects: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            let mut object = Map::new();
+
+            for (name, value) in feature_names.iter().zip(row.features.iter()) {
+                object.insert(name.clone(), Value::from(*value));
+            }
+
+            object.insert("class".to_owned(), Value::from(row.class.clone()));
+            object.insert(
+                "split".to_owned(),
+                Value::from(row.subset.map(|s| s.name())),
+            );
+            object.insert("probability".to_owned(), Value::from(row.probability));
+            object.insert("prediction".to_owned(), Value::from(row.prediction.clone()));
+            object.insert("loss".to_owned(), Value::from(row.loss));
+
+            Value::Object(object)
+        })
+        .collect();
+
+    let text = if lines {
+        objects
+            .iter()
+            .map(|object| object.to_string())
+            .collect::<Vec<String>>()
+            .join("\n")
+    } else {
+        serde_json::to_string_pretty(&objects).map_err(|error| error.to_string())?
+    };
+
+    std::fs::write(path, text + "\n").map_err(|error| format!("{}: {}", path, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    fn rows(class_names: &[String]) -> Vec<PredictionRow> {
+        (0..6)
+            .map(|i| PredictionRow {
+                features: vec![i as f64 + 0.5, 10.0 - i as f64],
+                class: class_names[i % 2].clone(),
+                subset: [Some(Subset::Train), Some(Subset::Test), None][i % 3],
+                probability: 0.25,
+                prediction: class_names[1].clone(),
+                loss: (i % 3 != 2).then_some(0.125),
+            })
+            .collect()
+    }
+
+    // Write the rows to a file of the format, then read it back like a dataset
+    fn round_trip(file_name: &str, write: impl Fn(&str, &[String], &[String], &[PredictionRow])) {
+        let feature_names = names(&["petal 'length'", "back\\slash"]);
+        let class_names = names(&["Iris 'Setosa'", "C:\\virginica"]);
+        let rows = rows(&class_names);
+
+        let path = std::env::temp_dir()
+            .join(format!("{}-{}", std::process::id(), file_name))
+            .to_string_lossy()
+            .to_string();
+        write(&path, &feature_names, &class_names, &rows);
+
+        let config = DatasetConfig {
+            path: path.clone(),
+            ..DatasetConfig::default()
+        };
+        let dataset = Dataset::load(&config);
+        std::fs::remove_file(&path).unwrap();
+        let dataset = dataset.unwrap();
+
+        assert_eq!(dataset.feature_names, feature_names);
+        assert_eq!(dataset.class_names, class_names);
+        assert_eq!(dataset.samples.len(), rows.len());
+
+        for (sample, row) in dataset.samples.iter().zip(rows.iter()) {
+            assert_eq!(sample.features, row.features);
+            assert_eq!(dataset.class_names[sample.class], row.class);
+        }
+    }
+
+    #[test]
+    fn arff_round_trip() {
+        round_trip("export.arff", |path, features, classes, rows| {
+            write_arff(path, "it's a \\ test", features, classes, rows).unwrap()
+        });
+    }
+
+    #[test]
+    fn json_round_trip() {
+        round_trip("export.json", |path, features, _, rows| {
+            write_json(path, features, rows, false).unwrap()
+        });
+    }
+
+    #[test]
+    fn json_lines_round_trip() {
+        round_trip("export.jsonl", |path, features, _, rows| {
+            write_json(path, features, rows, true).unwrap()
+        });
+    }
+
+    #[test]
+    fn split_fields_keeps_escaped_quotes() {
+        assert_eq!(
+            split_fields("1.5, 'it\\'s', \"a, b\", 'back\\\\slash'"),
+            names(&["1.5", "it's", "a, b", "back\\slash"])
+        );
+    }
+}
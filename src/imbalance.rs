@@ -0,0 +1,139 @@
+// Class imbalance experiments: make one class rare in the training split, then make up for it
+// by resampling the train data or by weighting the gradient of each class.
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::dataset::Sample;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resampling {
+    None,
+    // Repeat random samples of the minority target until both targets have as many samples
+    Oversample,
+    // Drop random samples of the majority target until both targets have as many samples
+    Undersample,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Weighting {
+    None,
+    // The weights given in the config
+    Manual,
+    // n / (2 * n_target), so both targets weigh the same in total
+    Balanced,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImbalanceConfig {
+    // Class made rare in the training split
+    pub downsample_class: Option<usize>,
+    // Fraction of its training samples that are kept
+    pub keep_ratio: f64,
+    pub resampling: Resampling,
+    pub weighting: Weighting,
+    // Weights of the targets 0 and 1 for the manual weighting
+    pub manual_weights: [f64; 2],
+    pub seed: u64,
+}
+
+impl Default for ImbalanceConfig {
+    fn default() -> Self {
+        Self {
+            downsample_class: None,
+            keep_ratio: 0.2,
+            resampling: Resampling::None,
+            weighting: Weighting::None,
+            manual_weights: [1.0, 1.0],
+            seed: 11,
+        }
+    }
+}
+
+// The indexes left after keeping only part of the samples of the downsampled class
+pub fn downsample(data: &[Sample], indexes: &[usize], config: &ImbalanceConfig) -> Vec<usize> {
+    let Some(class) = config.downsample_class else {
+        return indexes.to_vec();
+    };
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+
+    let of_class = indexes.iter().filter(|&&i| data[i].class == class).count();
+    // At least one sample is kept, so the class doesn't disappear
+    let keep = ((of_class as f64 * config.keep_ratio).round() as usize).max(1);
+
+    let mut dropped: Vec<usize> = indexes
+        .iter()
+        .copied()
+        .filter(|&i| data[i].class == class)
+        .collect();
+    dropped.shuffle(&mut rng);
+    dropped.truncate(of_class.saturating_sub(keep));
+
+    indexes
+        .iter()
+        .copied()
+        .filter(|i| !dropped.contains(i))
+        .collect()
+}
+
+pub fn target_counts(train_data: &[(Vec<f64>, f64)]) -> [usize; 2] {
+    let positives = train_data.iter().filter(|(_, t)| *t >= 0.5).count();
+    [train_data.len() - positives, positives]
+}
+
+// Positions in the train data after resampling, the same position can be repeated
+pub fn resample(train_data: &[(Vec<f64>, f64)], config: &ImbalanceConfig) -> Vec<usize> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+
+    let all: Vec<usize> = (0..train_data.len()).collect();
+    let (positives, negatives): (Vec<usize>, Vec<usize>) =
+        all.iter().partition(|&&i| train_data[i].1 >= 0.5);
+
+    if positives.is_empty() || negatives.is_empty() {
+        return all;
+    }
+
+    let (mut minority, mut majority) = if positives.len() < negatives.len() {
+        (positives, negatives)
+    } else {
+        (negatives, positives)
+    };
+
+    match config.resampling {
+        Resampling::None => return all,
+        Resampling::Oversample => {
+            let extra: Vec<usize> = (minority.len()..majority.len())
+                .map(|_| minority[rng.gen_range(0..minority.len())])
+                .collect();
+            minority.extend(extra);
+        }
+        Resampling::Undersample => {
+            majority.shuffle(&mut rng);
+            majority.truncate(minority.len());
+        }
+    }
+
+    let mut resampled = minority;
+    resampled.extend(majority);
+    resampled.shuffle(&mut rng);
+
+    resampled
+}
+
+pub fn class_weights(train_data: &[(Vec<f64>, f64)], config: &ImbalanceConfig) -> [f64; 2] {
+    match config.weighting {
+        Weighting::None => [1.0, 1.0],
+        Weighting::Manual => config.manual_weights,
+        Weighting::Balanced => {
+            let counts = target_counts(train_data);
+            let total = train_data.len() as f64;
+
+            counts.map(|count| {
+                if count == 0 {
+                    1.0
+                } else {
+                    total / (2.0 * count as f64)
+                }
+            })
+        }
+    }
+}
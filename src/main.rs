@@ -1,16 +1,27 @@
 use draw::update;
-use rand::seq::SliceRandom;
 
+mod calibration;
+mod cli;
 mod components;
+mod cross_validation;
+mod dataset;
 mod draw;
+mod error_screen;
+mod formats;
+mod imbalance;
 mod line_graph;
+mod model;
 mod neuron;
+mod perturbation;
+mod preprocessing;
+mod projection;
 mod separation_graph;
+mod split;
 mod state;
-mod train_data;
+mod statistics;
+mod synthetic;
 
-use raylib::prelude::*;
-use train_data::{Iris, IrisData};
+use dataset::{Dataset, Sample};
 
 pub const WINDOW_HEIGHT: f32 = 480.0 * 1.5;
 pub const WINDOW_WIDTH: f32 = 640.0 * 1.5;
@@ -18,9 +29,9 @@ pub const WINDOW_WIDTH: f32 = 640.0 * 1.5;
 pub struct State {
     pub line_graph: line_graph::LineGraph,
     pub separation_graph: separation_graph::SeparationGraph,
-    pub csv_data: Vec<IrisData>,
-    pub data: Vec<IrisData>,
-    pub test_data: Vec<(Vec<f64>, Iris)>,
+    pub csv_data: Dataset,
+    pub data: Vec<Sample>,
+    pub test_data: Vec<(Vec<f64>, usize)>,
     pub neuron: neuron::Neuron,
     pub text: [u8; 20],
     pub pause: bool,
@@ -28,16 +39,97 @@ pub struct State {
     pub update_hz: i32,
     pub iris_select: (bool, i32),
     pub train_data: Vec<(Vec<f64>, f64)>,
+    // Index of the sample of each row of the train data
+    pub train_samples: Vec<usize>,
     pub data_index: usize,
     pub generations: usize,
     pub outputs: (f64, f64, f64),
     pub loss: f64,
     pub target: Option<f64>,
-    pub iris_type: Iris,
+    // Positive class, the target 1
+    pub iris_type: usize,
     pub iris_type_component: (bool, i32),
+    // Negative class, the target 0. None means every class but the positive one.
+    pub negative_class: Option<usize>,
+    pub negative_component: (bool, i32),
+    pub panel_select: (bool, i32),
+    // The report of the last run, or why it couldn't run
+    pub cross_validation: Option<Result<cross_validation::CrossValidationReport, String>>,
+    pub cv_folds: i32,
+    pub cv_stratified: bool,
+    pub validation_data: Vec<(Vec<f64>, f64)>,
+    pub calibrator: calibration::Calibrator,
+    pub calibrator_select: i32,
+    pub scaler: preprocessing::Scaler,
+    pub scaling_select: (bool, i32),
+    // Result of the last model save or load, or export
+    pub status: Option<String>,
+    pub split: split::Split,
+    pub split_config: split::SplitConfig,
+    pub dataset_config: dataset::DatasetConfig,
+    pub synthetic: synthetic::SyntheticConfig,
+    pub synthetic_select: (bool, i32),
+    pub dataset_status: Option<String>,
+    pub stats_class: i32,
+    pub perturbation: perturbation::PerturbationConfig,
+    pub corruptions: Vec<perturbation::Corruption>,
+    pub imbalance: imbalance::ImbalanceConfig,
+    pub downsample_select: i32,
+    pub resampling_select: i32,
+    pub weighting_select: i32,
+    // Features of the graph, and of the neuron unless all of them are used
+    pub x_feature: (bool, i32),
+    pub y_feature: (bool, i32),
+    // View of the "All" features in the separation graph
+    pub projection: Option<projection::Projection>,
+    pub projection_select: i32,
+    // Values of the features that aren't on the axes of a slice, and which means they start at
+    pub slice_values: Vec<f64>,
+    pub slice_means_select: i32,
+    // Parallel coordinates: colour by species (0) or probability (1), the brushed range of each
+    // feature, and the feature and value where the brush being dragged started
+    pub parallel_color: i32,
+    pub brushes: Vec<Option<(f64, f64)>>,
+    pub brush_drag: Option<(usize, f64)>,
+    // Probability heatmap and its contours behind the separation graph
+    pub show_heatmap: bool,
+    pub show_contours: bool,
+    // Rows of the exports: all (0), train (1), validation (2) or test (3)
+    pub export_rows: i32,
+    // File of the exports, the format is picked from its extension
+    pub export_path: [u8; error_screen::PATH_SIZE],
+    pub export_path_edit: bool,
+    // Sample dragged on the separation graph
+    pub dragging: Option<usize>,
+    // Separation graph view: moved with the mouse, or kept as it is when locked
+    pub panning: bool,
+    pub lock_axes: bool,
+    // Sample pinned by clicking its point, highlighted in the other views too
+    pub selected_sample: Option<usize>,
+    // Size of the points of the separation graph by their loss
+    pub loss_size: bool,
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let cli::WindowOptions {
+        dataset: mut config,
+        split: split_config,
+        output,
+    } = match cli::parse(&args) {
+        Ok(cli::Command::Window(options)) => options,
+        // Headless commands run without opening the window
+        Ok(command) => {
+            cli::run(command);
+            return;
+        }
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, cli::USAGE);
+            std::process::exit(1);
+        }
+    };
+
     let (mut rl, thread) = raylib::init()
         .size(WINDOW_WIDTH as i32, WINDOW_HEIGHT as i32)
         // .resizable()
@@ -45,27 +137,71 @@ fn main() {
         .build();
 
     rl.set_target_fps(144);
-    let csv_data = IrisData::read_csv();
+    let csv_data = match Dataset::load_with_fallback(&config) {
+        Ok(csv_data) => csv_data,
+        Err(error) => match error_screen::run(&mut rl, &thread, &mut config, error) {
+            Some(csv_data) => csv_data,
+            None => return,
+        },
+    };
+
+    for skipped in csv_data.skipped.iter() {
+        eprintln!("Skipped {}", skipped);
+    }
+
+    if csv_data.source != config.path {
+        eprintln!("Using {}", csv_data.source);
+    }
+
+    rl.set_window_title(
+        &thread,
+        &format!("Iris Artificial Neuron - Demo ({})", csv_data.source),
+    );
+
+    // The last class is paired with the first one by default (Virginica vs Setosa for Iris)
+    let iris_type = csv_data.class_names.len() - 1;
+
+    let negative_class = Some(0);
+
+    let data = csv_data.get_data(iris_type, negative_class);
+
+    // Disjoint train, validation and test sets
+    let split = split::split(&data, &split_config);
 
-    let mut data = IrisData::get_data(&csv_data, Iris::Virginica);
+    let features = csv_data.selected_features(0);
 
-    let graph_data = IrisData::get_graph_data(&data);
+    // The data starts clean, see the perturbation panel
+    let corruptions = vec![perturbation::Corruption::None; data.len()];
 
-    data.shuffle(&mut rand::thread_rng());
+    let sepal_data = Dataset::get_graph_data(
+        &data,
+        &split.subsets,
+        &corruptions,
+        features[0],
+        features[1],
+    );
 
-    // The test data will have 5 of each flower (setosa and virginica). Then it will be 5 of setosa and 5 of virginica
+    let train_data =
+        Dataset::get_train_data(&Dataset::select(&data, &split.train), &features, iris_type);
+    let validation_data = Dataset::get_train_data(
+        &Dataset::select(&data, &split.validation),
+        &features,
+        iris_type,
+    );
 
-    let sepal_data = graph_data
-        .iter()
-        .map(|(x, y, _, _, c)| (*x, *y, *c))
-        .collect::<Vec<(f32, f32, Color)>>();
+    // Z-score scaling by default, fitted on the training split only
+    let scaler = preprocessing::Scaler::fit(
+        preprocessing::Scaling::Standard,
+        &train_data,
+        features.len(),
+    );
+    let train_data = scaler.transform_data(&train_data);
+    let validation_data = scaler.transform_data(&validation_data);
 
-    let train_data = data
-        .iter()
-        .map(|d| (vec![d.sepal_length, d.sepal_width], d.species.into()))
-        .collect();
+    let test_data = Dataset::get_test_data(&Dataset::select(&data, &split.test));
 
-    let test_data = IrisData::get_test_data(&data, 3);
+    // Nothing is brushed in the parallel coordinates
+    let brushes = vec![None; csv_data.feature_names.len()];
 
     let mut state: State = State {
         line_graph: line_graph::LineGraph::new(40.0, 40.0, 300.0, 300.0),
@@ -74,27 +210,72 @@ fn main() {
             380.0,
             300.0,
             300.0,
-            "SepalLength".to_owned(),
-            "SepalWidth".to_owned(),
+            csv_data.feature_names[features[0]].clone(),
+            csv_data.feature_names[features[1]].clone(),
             sepal_data,
         ),
         data,
         csv_data,
         test_data,
-        neuron: neuron::Neuron::new(2, neuron::SIGMOID, 0.5),
+        neuron: neuron::Neuron::new(features.len(), neuron::SIGMOID, 0.5),
         text: [0; 20],
         pause: true,
         last_event_time: 0.0,
         update_hz: 1,
         iris_select: (false, 0),
         train_data,
+        train_samples: split.train.clone(),
         data_index: 0,
         generations: 0,
         outputs: (0.0, 0.0, 0.0),
         target: None,
         loss: 0.0,
-        iris_type: Iris::Virginica,
-        iris_type_component: (false, 0),
+        iris_type,
+        iris_type_component: (false, iris_type as i32),
+        negative_class,
+        negative_component: (false, 0),
+        panel_select: (false, 0),
+        cross_validation: None,
+        cv_folds: cross_validation::DEFAULT_FOLDS as i32,
+        cv_stratified: true,
+        validation_data,
+        calibrator: calibration::Calibrator::None,
+        calibrator_select: 0,
+        scaler,
+        scaling_select: (false, 1),
+        status: None,
+        split,
+        split_config,
+        dataset_config: config,
+        synthetic: synthetic::SyntheticConfig::default(),
+        synthetic_select: (false, 0),
+        dataset_status: None,
+        stats_class: 0,
+        perturbation: perturbation::PerturbationConfig::default(),
+        corruptions,
+        imbalance: imbalance::ImbalanceConfig::default(),
+        downsample_select: 0,
+        resampling_select: 0,
+        weighting_select: 0,
+        x_feature: (false, features[0] as i32),
+        y_feature: (false, features[1] as i32),
+        projection: None,
+        projection_select: 0,
+        slice_values: vec![],
+        slice_means_select: 2,
+        parallel_color: 0,
+        brushes,
+        brush_drag: None,
+        show_heatmap: false,
+        show_contours: true,
+        export_rows: 0,
+        export_path: error_screen::path_buffer(&output),
+        export_path_edit: false,
+        dragging: None,
+        panning: false,
+        lock_axes: false,
+        selected_sample: None,
+        loss_size: false,
     };
 
     // state
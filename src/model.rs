@@ -0,0 +1,233 @@
+use std::{fmt::Write, str::FromStr};
+
+use crate::{
+    neuron::SIGMOID,
+    preprocessing::{Scaler, Scaling},
+    split::SplitConfig,
+};
+
+pub const MODEL_PATH: &str = "./model.txt";
+
+// A trained neuron together with the scaling its inputs need, and the classes and split it was
+// trained on
+#[derive(Debug, Clone)]
+pub struct Model {
+    pub feature_names: Vec<String>,
+    pub scaler: Scaler,
+    pub weights: Vec<f64>,
+    pub bias: f64,
+    // Class names of the targets 1 and 0. No negative class means every class but the positive one.
+    pub positive: String,
+    pub negative: Option<String>,
+    pub split: SplitConfig,
+}
+
+fn join(values: &[f64]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn parse_values(values: &str, key: &str) -> Result<Vec<f64>, String> {
+    values
+        .split_whitespace()
+        .map(|v| {
+            v.parse()
+                .map_err(|_| format!("Invalid number '{}' for {}", v, key))
+        })
+        .collect()
+}
+
+fn parse_value<T: FromStr>(values: &str, key: &str) -> Result<T, String> {
+    values
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for {}", values.trim(), key))
+}
+
+impl Model {
+    // One "key values" pair per line, e.g. "weights 0.5 -1.2"
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        writeln!(text, "features {}", self.feature_names.join(",")).unwrap();
+        writeln!(text, "scaling {}", self.scaler.scaling.name()).unwrap();
+        writeln!(text, "center {}", join(&self.scaler.center)).unwrap();
+        writeln!(text, "scale {}", join(&self.scaler.scale)).unwrap();
+        writeln!(text, "weights {}", join(&self.weights)).unwrap();
+        writeln!(text, "bias {}", self.bias).unwrap();
+        writeln!(text, "positive {}", self.positive).unwrap();
+        writeln!(
+            text,
+            "negative {}",
+            self.negative.as_deref().unwrap_or("rest")
+        )
+        .unwrap();
+        writeln!(text, "train_ratio {}", self.split.train_ratio).unwrap();
+        writeln!(text, "validation_ratio {}", self.split.validation_ratio).unwrap();
+        writeln!(text, "seed {}", self.split.seed).unwrap();
+        writeln!(text, "stratified {}", self.split.stratified).unwrap();
+
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Model, String> {
+        let mut feature_names = vec![];
+        let mut scaling = None;
+        let mut center = vec![];
+        let mut scale = vec![];
+        let mut weights = vec![];
+        let mut bias = None;
+        let mut positive = None;
+        let mut negative = None;
+        let mut train_ratio = None;
+        let mut validation_ratio = None;
+        let mut seed = None;
+        let mut stratified = None;
+
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            let (key, values) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+
+            match key {
+                "features" => feature_names = values.split(',').map(|f| f.to_owned()).collect(),
+                "scaling" => {
+                    scaling = Some(
+                        Scaling::from_name(values.trim())
+                            .ok_or_else(|| format!("Unknown scaling '{}'", values.trim()))?,
+                    )
+                }
+                "center" => center = parse_values(values, key)?,
+                "scale" => scale = parse_values(values, key)?,
+                "weights" => weights = parse_values(values, key)?,
+                "bias" => {
+                    bias = Some(
+                        values
+                            .trim()
+                            .parse()
+                            .map_err(|_| format!("Invalid number '{}' for bias", values))?,
+                    )
+                }
+                "positive" => positive = Some(values.trim().to_owned()),
+                "negative" => {
+                    negative = Some(match values.trim() {
+                        "rest" => None,
+                        name => Some(name.to_owned()),
+                    })
+                }
+                "train_ratio" => train_ratio = Some(parse_value(values, key)?),
+                "validation_ratio" => validation_ratio = Some(parse_value(values, key)?),
+                "seed" => seed = Some(parse_value(values, key)?),
+                "stratified" => stratified = Some(parse_value(values, key)?),
+                _ => return Err(format!("Unknown key '{}'", key)),
+            }
+        }
+
+        let inputs = weights.len();
+
+        if feature_names.len() != inputs || center.len() != inputs || scale.len() != inputs {
+            return Err("The features, scaler and weights don't have the same length".to_owned());
+        }
+
+        Ok(Model {
+            feature_names,
+            scaler: Scaler {
+                scaling: scaling.ok_or("Missing scaling")?,
+                center,
+                scale,
+            },
+            weights,
+            bias: bias.ok_or("Missing bias")?,
+            positive: positive.ok_or("Missing positive class")?,
+            negative: negative.ok_or("Missing negative class")?,
+            split: SplitConfig {
+                train_ratio: train_ratio.ok_or("Missing train_ratio")?,
+                validation_ratio: validation_ratio.ok_or("Missing validation_ratio")?,
+                seed: seed.ok_or("Missing seed")?,
+                stratified: stratified.ok_or("Missing stratified")?,
+            },
+        })
+    }
+
+    // e.g. "Iris Virginica vs Iris Setosa" or "Iris Virginica vs rest"
+    pub fn pair_name(&self) -> String {
+        format!(
+            "{} vs {}",
+            self.positive,
+            self.negative.as_deref().unwrap_or("rest")
+        )
+    }
+
+    // Output of the neuron for inputs in the units of the data
+    pub fn predict(&self, inputs: &[f64]) -> f64 {
+        let scaled = self.scaler.transform(inputs);
+        let sum: f64 = scaled.iter().zip(&self.weights).map(|(x, w)| x * w).sum();
+
+        // The demo neuron always uses the sigmoid
+        (SIGMOID.function)(sum + self.bias)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_text()).map_err(|error| format!("{}: {}", path, error))
+    }
+
+    pub fn load(path: &str) -> Result<Model, String> {
+        let text = std::fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+        Model::from_text(&text).map_err(|error| format!("{}: {}", path, error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(negative: Option<&str>) -> Model {
+        Model {
+            feature_names: vec!["petal_length".to_owned(), "petal_width".to_owned()],
+            scaler: Scaler {
+                scaling: Scaling::Standard,
+                center: vec![4.0, 1.25],
+                scale: vec![1.5, 0.75],
+            },
+            weights: vec![2.0, -1.5],
+            bias: -0.5,
+            positive: "Iris Virginica".to_owned(),
+            negative: negative.map(|n| n.to_owned()),
+            split: SplitConfig {
+                train_ratio: 0.5,
+                validation_ratio: 0.25,
+                stratified: false,
+                seed: 7,
+            },
+        }
+    }
+
+    #[test]
+    fn text_round_trip_keeps_classes_and_split() {
+        for negative in [Some("Iris Setosa"), None] {
+            let model = model(negative);
+            let read = Model::from_text(&model.to_text()).unwrap();
+
+            assert_eq!(read.feature_names, model.feature_names);
+            assert_eq!(read.scaler.center, model.scaler.center);
+            assert_eq!(read.scaler.scale, model.scaler.scale);
+            assert_eq!(read.weights, model.weights);
+            assert_eq!(read.bias, model.bias);
+            assert_eq!(read.pair_name(), model.pair_name());
+            assert_eq!(read.split, model.split);
+        }
+    }
+
+    #[test]
+    fn model_without_classes_is_rejected() {
+        let text: String = model(None)
+            .to_text()
+            .lines()
+            .filter(|line| !line.starts_with("positive"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+
+        assert!(Model::from_text(&text).is_err());
+    }
+}
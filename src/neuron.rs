@@ -23,17 +23,29 @@ pub struct Neuron {
     pub learning_rate: f64,
     pub loss: f64,
     pub amount: f64,
+    // Gradient weight of the targets 0 and 1, to make up for an imbalanced training set
+    pub class_weights: [f64; 2],
 }
 
 impl Neuron {
     pub fn new(inputs: usize, activation: Activation, learning_rate: f64) -> Neuron {
+        Neuron::with_rng(inputs, activation, learning_rate, &mut rand::thread_rng())
+    }
+
+    // The initial weights are drawn from the given generator, so a seeded one gives the same neuron
+    pub fn with_rng(
+        inputs: usize,
+        activation: Activation,
+        learning_rate: f64,
+        rng: &mut impl Rng,
+    ) -> Neuron {
         let mut weights = Vec::new();
 
         for _ in 0..inputs {
-            weights.push(rand::thread_rng().gen_range(0.0..1.0));
+            weights.push(rng.gen_range(0.0..1.0));
         }
 
-        let biase = rand::thread_rng().gen_range(0.0..1.0);
+        let biase = rng.gen_range(0.0..1.0);
 
         // let weights = vec![0.5, 0.5];
         // let biase = 0.5;
@@ -46,6 +58,7 @@ impl Neuron {
             learning_rate,
             loss: 0.0,
             amount: 0.0,
+            class_weights: [1.0, 1.0],
         }
     }
 
@@ -65,7 +78,8 @@ impl Neuron {
 
     pub fn back_propagate(&mut self, output: f64, target: f64) -> (f64, f64) {
         let error = target - output;
-        let gradient = (self.activation.derivative)(output) * error * self.learning_rate;
+        let weight = self.class_weights[(target >= 0.5) as usize];
+        let gradient = (self.activation.derivative)(output) * error * self.learning_rate * weight;
 
         for i in 0..self.inputs {
             self.weights[i] += gradient * self.data[i];
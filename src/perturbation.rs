@@ -0,0 +1,129 @@
+// Dirty data for robustness experiments. Only the training samples are perturbed, so the
+// validation and test sets still measure the neuron against clean data.
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{dataset::Sample, statistics::Summary, synthetic::gaussian};
+
+#[derive(Debug, Clone)]
+pub struct PerturbationConfig {
+    // Standard deviation of the Gaussian noise added to every feature, in the units of the data
+    pub noise: f64,
+    // Fraction of the samples that get the label of the other class
+    pub flip_rate: f64,
+    // Fraction of the samples moved far away from the others
+    pub outlier_rate: f64,
+    pub seed: u64,
+}
+
+impl Default for PerturbationConfig {
+    fn default() -> Self {
+        Self {
+            noise: 0.0,
+            flip_rate: 0.0,
+            outlier_rate: 0.0,
+            seed: 7,
+        }
+    }
+}
+
+impl PerturbationConfig {
+    pub fn is_clean(&self) -> bool {
+        self.noise == 0.0 && self.flip_rate == 0.0 && self.outlier_rate == 0.0
+    }
+}
+
+// What happened to a sample. A sample can be both noisy and flipped, the strongest change is kept.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Corruption {
+    None,
+    Noise,
+    Flip,
+    Outlier,
+}
+
+// Outliers are put this many standard deviations away from the mean of each feature
+const OUTLIER_DISTANCE: f64 = 5.0;
+
+// Perturb the samples at the given indexes. Returns a copy of the data and the corruption of every sample.
+pub fn perturb(
+    data: &[Sample],
+    indexes: &[usize],
+    positive: usize,
+    negative: Option<usize>,
+    config: &PerturbationConfig,
+) -> (Vec<Sample>, Vec<Corruption>) {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+
+    let mut perturbed = data.to_vec();
+    let mut corruptions = vec![Corruption::None; data.len()];
+
+    if indexes.is_empty() || config.is_clean() {
+        return (perturbed, corruptions);
+    }
+
+    if config.noise > 0.0 {
+        for &i in indexes {
+            for value in perturbed[i].features.iter_mut() {
+                *value += config.noise * gaussian(&mut rng);
+            }
+            corruptions[i] = Corruption::Noise;
+        }
+    }
+
+    // The label of the other class, or of a random other class in one-vs-rest
+    let others: Vec<usize> = {
+        let mut classes: Vec<usize> = data
+            .iter()
+            .map(|s| s.class)
+            .filter(|&c| c != positive)
+            .collect();
+        classes.sort();
+        classes.dedup();
+        classes
+    };
+
+    let mut shuffled = indexes.to_vec();
+    shuffled.shuffle(&mut rng);
+
+    let flips = (indexes.len() as f64 * config.flip_rate).round() as usize;
+
+    for &i in shuffled.iter().take(flips) {
+        perturbed[i].class = if perturbed[i].class == positive {
+            match negative {
+                Some(negative) => negative,
+                None => *others.choose(&mut rng).unwrap_or(&positive),
+            }
+        } else {
+            positive
+        };
+        corruptions[i] = Corruption::Flip;
+    }
+
+    shuffled.shuffle(&mut rng);
+
+    let outliers = (indexes.len() as f64 * config.outlier_rate).round() as usize;
+    let features = data[indexes[0]].features.len();
+
+    let summaries: Vec<Summary> = (0..features)
+        .filter_map(|f| {
+            Summary::from_values(
+                &indexes
+                    .iter()
+                    .map(|&i| data[i].features[f])
+                    .collect::<Vec<f64>>(),
+            )
+        })
+        .collect();
+
+    for &i in shuffled.iter().take(outliers) {
+        for (value, summary) in perturbed[i].features.iter_mut().zip(summaries.iter()) {
+            let side = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+            let distance = OUTLIER_DISTANCE * rng.gen_range(0.8..1.2);
+
+            *value = summary.mean + side * distance * summary.std.max(f64::EPSILON);
+        }
+        corruptions[i] = Corruption::Outlier;
+    }
+
+    (perturbed, corruptions)
+}
@@ -0,0 +1,223 @@
+// Feature scaling applied before the data reaches the neuron. The scaler is fitted on the
+// training split only, so the held-out and test samples don't leak into it.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scaling {
+    None,
+    // (x - mean) / std
+    Standard,
+    // (x - min) / (max - min)
+    MinMax,
+    // (x - median) / IQR, less sensitive to outliers
+    Robust,
+}
+
+pub const SCALINGS: [Scaling; 4] = [
+    Scaling::None,
+    Scaling::Standard,
+    Scaling::MinMax,
+    Scaling::Robust,
+];
+
+impl Scaling {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scaling::None => "none",
+            Scaling::Standard => "standard",
+            Scaling::MinMax => "minmax",
+            Scaling::Robust => "robust",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Scaling> {
+        SCALINGS.iter().copied().find(|s| s.name() == name)
+    }
+}
+
+// Every feature is mapped to (x - center) / scale
+#[derive(Debug, Clone)]
+pub struct Scaler {
+    pub scaling: Scaling,
+    pub center: Vec<f64>,
+    pub scale: Vec<f64>,
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+// Linear interpolation between the closest ranks of sorted values
+pub fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let low = position.floor() as usize;
+    let high = position.ceil() as usize;
+
+    sorted[low] + (sorted[high] - sorted[low]) * (position - low as f64)
+}
+
+impl Scaler {
+    pub fn identity(inputs: usize) -> Scaler {
+        Scaler {
+            scaling: Scaling::None,
+            center: vec![0.0; inputs],
+            scale: vec![1.0; inputs],
+        }
+    }
+
+    // Without data, e.g. when every training sample was deleted, the features are kept as they are
+    pub fn fit(scaling: Scaling, data: &[(Vec<f64>, f64)], inputs: usize) -> Scaler {
+        if scaling == Scaling::None || data.is_empty() {
+            return Scaler::identity(inputs);
+        }
+
+        let mut center = vec![];
+        let mut scale = vec![];
+
+        for i in 0..inputs {
+            let mut values: Vec<f64> = data.iter().map(|(inputs, _)| inputs[i]).collect();
+            values.sort_by(|a, b| a.total_cmp(b));
+
+            let (c, s) = match scaling {
+                Scaling::Standard => {
+                    let mean = mean(&values);
+                    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>()
+                        / values.len() as f64;
+                    (mean, variance.sqrt())
+                }
+                Scaling::MinMax => (values[0], values[values.len() - 1] - values[0]),
+                Scaling::Robust => (
+                    quantile(&values, 0.5),
+                    quantile(&values, 0.75) - quantile(&values, 0.25),
+                ),
+                Scaling::None => (0.0, 1.0),
+            };
+
+            center.push(c);
+            // A constant feature is only centered
+            scale.push(if s > f64::EPSILON { s } else { 1.0 });
+        }
+
+        Scaler {
+            scaling,
+            center,
+            scale,
+        }
+    }
+
+    pub fn transform(&self, inputs: &[f64]) -> Vec<f64> {
+        inputs
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (x - self.center[i]) / self.scale[i])
+            .collect()
+    }
+
+    pub fn inverse_transform(&self, inputs: &[f64]) -> Vec<f64> {
+        inputs
+            .iter()
+            .enumerate()
+            .map(|(i, x)| x * self.scale[i] + self.center[i])
+            .collect()
+    }
+
+    pub fn transform_data(&self, data: &[(Vec<f64>, f64)]) -> Vec<(Vec<f64>, f64)> {
+        data.iter()
+            .map(|(inputs, target)| (self.transform(inputs), *target))
+            .collect()
+    }
+
+    // The hyperplane w·z + b = 0 in scaled units is (w / s)·x + b - Σ w c / s = 0 in original units
+    pub fn unscale_line(&self, weights: &[f64], bias: f64) -> (Vec<f64>, f64) {
+        let weights: Vec<f64> = weights
+            .iter()
+            .enumerate()
+            .map(|(i, w)| w / self.scale[i])
+            .collect();
+
+        let bias = bias
+            - weights
+                .iter()
+                .enumerate()
+                .map(|(i, w)| w * self.center[i])
+                .sum::<f64>();
+
+        (weights, bias)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data() -> Vec<(Vec<f64>, f64)> {
+        vec![
+            (vec![1.0, 10.0, 3.0], 0.0),
+            (vec![2.0, 30.0, 3.0], 1.0),
+            (vec![4.0, 20.0, 3.0], 0.0),
+            (vec![7.0, 60.0, 3.0], 1.0),
+        ]
+    }
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-9, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn inverse_transform_undoes_transform() {
+        for scaling in SCALINGS {
+            let scaler = Scaler::fit(scaling, &data(), 3);
+
+            for (inputs, _) in data() {
+                assert_close(
+                    &scaler.inverse_transform(&scaler.transform(&inputs)),
+                    &inputs,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn empty_data_gives_identity_of_every_feature() {
+        for scaling in SCALINGS {
+            let scaler = Scaler::fit(scaling, &[], 3);
+
+            assert_close(&scaler.transform(&[1.0, 2.0, 3.0]), &[1.0, 2.0, 3.0]);
+        }
+    }
+
+    #[test]
+    fn min_max_maps_to_unit_range() {
+        let scaler = Scaler::fit(Scaling::MinMax, &data(), 3);
+
+        assert_close(&scaler.transform(&[1.0, 10.0, 3.0]), &[0.0, 0.0, 0.0]);
+        assert_close(&scaler.transform(&[7.0, 60.0, 3.0]), &[1.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn unscale_line_gives_same_outputs() {
+        let weights = [0.5, -1.5, 2.0];
+        let bias = 0.25;
+
+        for scaling in SCALINGS {
+            let scaler = Scaler::fit(scaling, &data(), 3);
+            let (unscaled, unscaled_bias) = scaler.unscale_line(&weights, bias);
+
+            for (inputs, _) in data() {
+                let scaled = scaler.transform(&inputs);
+                let expected: f64 =
+                    weights.iter().zip(&scaled).map(|(w, x)| w * x).sum::<f64>() + bias;
+                let output: f64 = unscaled
+                    .iter()
+                    .zip(&inputs)
+                    .map(|(w, x)| w * x)
+                    .sum::<f64>()
+                    + unscaled_bias;
+
+                assert!((expected - output).abs() < 1e-9);
+            }
+        }
+    }
+}
@@ -0,0 +1,427 @@
+// Two-dimensional views of more than two features. The features are standardized first, so the
+// larger ones don't dominate, then projected on two axes of that space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    // The two directions of largest variance
+    Pca,
+    // The direction that best separates the two targets, then the largest variance left
+    Lda,
+    // Two of the features, with the others fixed
+    Slice,
+}
+
+pub const METHODS: [Method; 3] = [Method::Pca, Method::Lda, Method::Slice];
+
+#[derive(Debug, Clone)]
+pub struct Projection {
+    pub method: Method,
+    pub mean: Vec<f64>,
+    pub std: Vec<f64>,
+    // Unit vectors in the standardized space
+    pub axes: [Vec<f64>; 2],
+    // Fraction of the total variance along each axis
+    pub explained: [f64; 2],
+    pub labels: [String; 2],
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalize(v: &[f64]) -> Vec<f64> {
+    let norm = dot(v, v).sqrt().max(f64::EPSILON);
+    v.iter().map(|x| x / norm).collect()
+}
+
+fn mat_vec(m: &[Vec<f64>], v: &[f64]) -> Vec<f64> {
+    m.iter().map(|row| dot(row, v)).collect()
+}
+
+// Rotate the values at p and q of a row by the angle with the cosine c and the sine s
+fn rotate(row: &mut [f64], p: usize, q: usize, c: f64, s: f64) {
+    let (x, y) = (row[p], row[q]);
+    row[p] = c * x - s * y;
+    row[q] = s * x + c * y;
+}
+
+// Eigenvalues and eigenvectors of a symmetric matrix with the Jacobi method, largest first
+fn eigen(matrix: &[Vec<f64>]) -> Vec<(f64, Vec<f64>)> {
+    let n = matrix.len();
+    let mut a = matrix.to_vec();
+    let mut v: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+
+    for _ in 0..100 {
+        let off: f64 = (0..n)
+            .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j].powi(2))
+            .sum();
+
+        if off < 1e-18 {
+            break;
+        }
+
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q].abs() < 1e-15 {
+                    continue;
+                }
+
+                // Rotate rows and columns p and q so a[p][q] becomes zero
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let t = if theta == 0.0 { 1.0 } else { t };
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+
+                for row in a.iter_mut() {
+                    rotate(row, p, q, c, s);
+                }
+
+                let (row_p, row_q) = (a[p].clone(), a[q].clone());
+                for (k, (apk, aqk)) in row_p.into_iter().zip(row_q).enumerate() {
+                    a[p][k] = c * apk - s * aqk;
+                    a[q][k] = s * apk + c * aqk;
+                }
+
+                for row in v.iter_mut() {
+                    rotate(row, p, q, c, s);
+                }
+            }
+        }
+    }
+
+    let mut pairs: Vec<(f64, Vec<f64>)> = (0..n)
+        .map(|i| (a[i][i], v.iter().map(|row| row[i]).collect()))
+        .collect();
+    pairs.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    pairs
+}
+
+// Solve m x = b by Gaussian elimination with partial pivoting
+fn solve(m: &[Vec<f64>], b: &[f64]) -> Vec<f64> {
+    let n = b.len();
+    let mut a: Vec<Vec<f64>> = m
+        .iter()
+        .zip(b)
+        .map(|(row, &b)| {
+            let mut row = row.clone();
+            row.push(b);
+            row
+        })
+        .collect();
+
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+            .unwrap();
+        a.swap(col, pivot);
+
+        let diagonal = a[col][col];
+        if diagonal.abs() < f64::EPSILON {
+            continue;
+        }
+
+        for row in 0..n {
+            if row != col {
+                let factor = a[row][col] / diagonal;
+                let pivot_row = a[col].clone();
+                for (value, pivot) in a[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                    *value -= factor * pivot;
+                }
+            }
+        }
+    }
+
+    (0..n)
+        .map(|i| {
+            if a[i][i].abs() < f64::EPSILON {
+                0.0
+            } else {
+                a[i][n] / a[i][i]
+            }
+        })
+        .collect()
+}
+
+// Mean of the samples of the target 1, or of the target 0
+pub fn target_mean(data: &[(Vec<f64>, f64)], positive: bool) -> Option<Vec<f64>> {
+    let samples: Vec<&Vec<f64>> = data
+        .iter()
+        .filter(|(_, t)| (*t >= 0.5) == positive)
+        .map(|(x, _)| x)
+        .collect();
+
+    let n = samples.first()?.len();
+
+    Some(
+        (0..n)
+            .map(|i| samples.iter().map(|x| x[i]).sum::<f64>() / samples.len() as f64)
+            .collect(),
+    )
+}
+
+fn covariance(data: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let n = data[0].len();
+    let count = data.len() as f64;
+
+    (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| data.iter().map(|x| x[i] * x[j]).sum::<f64>() / count)
+                .collect()
+        })
+        .collect()
+}
+
+impl Projection {
+    // Fitted on the (inputs, target) pairs in the units of the data
+    pub fn fit(method: Method, data: &[(Vec<f64>, f64)]) -> Option<Projection> {
+        let n = data.first()?.0.len();
+
+        if n < 2 {
+            return None;
+        }
+
+        let count = data.len() as f64;
+        let mean: Vec<f64> = (0..n)
+            .map(|i| data.iter().map(|(x, _)| x[i]).sum::<f64>() / count)
+            .collect();
+        let std: Vec<f64> = (0..n)
+            .map(|i| {
+                let variance = data
+                    .iter()
+                    .map(|(x, _)| (x[i] - mean[i]).powi(2))
+                    .sum::<f64>()
+                    / count;
+                // A constant feature is only centered
+                if variance > f64::EPSILON {
+                    variance.sqrt()
+                } else {
+                    1.0
+                }
+            })
+            .collect();
+
+        let standardized: Vec<Vec<f64>> = data
+            .iter()
+            .map(|(x, _)| (0..n).map(|i| (x[i] - mean[i]) / std[i]).collect())
+            .collect();
+
+        let cov = covariance(&standardized);
+        let total: f64 = (0..n).map(|i| cov[i][i]).sum::<f64>().max(f64::EPSILON);
+
+        let first = match method {
+            Method::Pca => eigen(&cov)[0].1.clone(),
+            // A slice isn't fitted, see Projection::slice
+            Method::Slice => return None,
+            Method::Lda => {
+                let standardize = |m: Vec<f64>| -> Vec<f64> {
+                    m.iter()
+                        .enumerate()
+                        .map(|(i, x)| (x - mean[i]) / std[i])
+                        .collect()
+                };
+
+                let (Some(m1), Some(m0)) = (target_mean(data, true), target_mean(data, false))
+                else {
+                    return Projection::fit(Method::Pca, data).map(|p| Projection { method, ..p });
+                };
+                let (m1, m0) = (standardize(m1), standardize(m0));
+
+                // Within-class scatter, with a small ridge so it can always be inverted
+                let centered: Vec<Vec<f64>> = standardized
+                    .iter()
+                    .zip(data)
+                    .map(|(z, (_, t))| {
+                        let m = if *t >= 0.5 { &m1 } else { &m0 };
+                        z.iter().zip(m).map(|(a, b)| a - b).collect()
+                    })
+                    .collect();
+                let mut within = covariance(&centered);
+                for (i, row) in within.iter_mut().enumerate() {
+                    row[i] += 1e-6;
+                }
+
+                let difference: Vec<f64> = m1.iter().zip(&m0).map(|(a, b)| a - b).collect();
+                normalize(&solve(&within, &difference))
+            }
+        };
+
+        // The largest variance left once the first axis is removed
+        let project_out = |v: &[f64]| -> Vec<f64> {
+            let d = dot(v, &first);
+            v.iter().zip(&first).map(|(x, f)| x - d * f).collect()
+        };
+        // (I - f fᵀ) C (I - f fᵀ), one column at a time. It's symmetric, so the columns are the rows.
+        let residual: Vec<Vec<f64>> = (0..n)
+            .map(|i| project_out(&mat_vec(&cov, &project_out(&unit(n, i)))))
+            .collect();
+        let second = normalize(&project_out(&eigen(&residual)[0].1));
+
+        let explained = [
+            dot(&first, &mat_vec(&cov, &first)) / total,
+            dot(&second, &mat_vec(&cov, &second)) / total,
+        ];
+
+        // The second LDA axis is the first principal component of what LD1 leaves
+        let (first_name, second_name) = match method {
+            Method::Lda => ("LD1", "Residual PC1"),
+            _ => ("PC1", "PC2"),
+        };
+
+        Some(Projection {
+            method,
+            mean,
+            std,
+            labels: [
+                format!("{} ({:.1}%)", first_name, explained[0] * 100.0),
+                format!("{} ({:.1}%)", second_name, explained[1] * 100.0),
+            ],
+            axes: [first, second],
+            explained,
+        })
+    }
+
+    // The plane through the given values where only the features x and y change
+    pub fn slice(x: usize, y: usize, values: &[f64], names: [String; 2]) -> Projection {
+        let n = values.len();
+
+        let mut origin = values.to_vec();
+        origin[x] = 0.0;
+        origin[y] = 0.0;
+
+        Projection {
+            method: Method::Slice,
+            mean: origin,
+            std: vec![1.0; n],
+            axes: [unit(n, x), unit(n, y)],
+            explained: [0.0, 0.0],
+            labels: names,
+        }
+    }
+
+    pub fn axis_names(&self) -> (String, String) {
+        (self.labels[0].clone(), self.labels[1].clone())
+    }
+
+    pub fn project(&self, inputs: &[f64]) -> [f64; 2] {
+        let z: Vec<f64> = inputs
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (x - self.mean[i]) / self.std[i])
+            .collect();
+
+        [dot(&z, &self.axes[0]), dot(&z, &self.axes[1])]
+    }
+
+    // The point of the plane at the given coordinates, in the units of the data
+    pub fn unproject(&self, point: [f64; 2]) -> Vec<f64> {
+        (0..self.mean.len())
+            .map(|i| {
+                self.mean[i]
+                    + self.std[i] * (point[0] * self.axes[0][i] + point[1] * self.axes[1][i])
+            })
+            .collect()
+    }
+
+    // The points of the plane are x = mean + std (a u1 + b u2), so the hyperplane w·x + b = 0
+    // crosses it on the line (w·std u1) a + (w·std u2) b + w·mean + b = 0
+    pub fn project_line(&self, weights: &[f64], bias: f64) -> (Vec<f64>, f64) {
+        let along = |axis: &[f64]| -> f64 {
+            weights
+                .iter()
+                .enumerate()
+                .map(|(i, w)| w * self.std[i] * axis[i])
+                .sum()
+        };
+
+        (
+            vec![along(&self.axes[0]), along(&self.axes[1])],
+            dot(weights, &self.mean) + bias,
+        )
+    }
+}
+
+fn unit(n: usize, i: usize) -> Vec<f64> {
+    (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn eigen_gives_orthonormal_vectors() {
+        // Eigenvalues 2 + √2, 2 and 2 - √2
+        let matrix = vec![
+            vec![2.0, -1.0, 0.0],
+            vec![-1.0, 2.0, -1.0],
+            vec![0.0, -1.0, 2.0],
+        ];
+        let pairs = eigen(&matrix);
+
+        let values: Vec<f64> = pairs.iter().map(|(value, _)| *value).collect();
+        assert_close(values[0], 2.0 + 2f64.sqrt());
+        assert_close(values[1], 2.0);
+        assert_close(values[2], 2.0 - 2f64.sqrt());
+
+        for (i, (value, vector)) in pairs.iter().enumerate() {
+            let product = mat_vec(&matrix, vector);
+            for (m, v) in product.iter().zip(vector) {
+                assert_close(*m, value * v);
+            }
+
+            for (j, (_, other)) in pairs.iter().enumerate() {
+                assert_close(dot(vector, other), if i == j { 1.0 } else { 0.0 });
+            }
+        }
+    }
+
+    #[test]
+    fn solve_linear_system() {
+        let m = vec![
+            vec![0.0, 2.0, 1.0],
+            vec![1.0, 1.0, 0.0],
+            vec![2.0, 0.0, 3.0],
+        ];
+        let x = solve(&m, &[7.0, 3.0, 11.0]);
+
+        for (value, expected) in x.iter().zip([1.0, 2.0, 3.0]) {
+            assert_close(*value, expected);
+        }
+    }
+
+    #[test]
+    fn first_lda_axis_follows_mean_difference() {
+        // The classes only differ along the first feature, with the same spread in every direction
+        let mut data = vec![];
+        for target in [0.0, 1.0] {
+            for i in 0..27 {
+                let noise = [i % 3, i / 3 % 3, i / 9].map(|n| n as f64 - 1.0);
+                let shift = if target >= 0.5 { 1.0 } else { -1.0 };
+                data.push((vec![shift + noise[0], noise[1], 5.0 + noise[2]], target));
+            }
+        }
+
+        let projection = Projection::fit(Method::Lda, &data).unwrap();
+        let [first, second] = &projection.axes;
+
+        assert_close(first[0].abs(), 1.0);
+        assert_close(dot(first, first), 1.0);
+        assert_close(dot(second, second), 1.0);
+        assert_close(dot(first, second), 0.0);
+
+        // LD1 points from the negative class to the positive one
+        let positive = projection.project(&[1.0, 0.0, 5.0]);
+        let negative = projection.project(&[-1.0, 0.0, 5.0]);
+        assert!(positive[0] > negative[0]);
+        assert_close(positive[1], negative[1]);
+    }
+}
@@ -3,7 +3,28 @@ use std::ops::Add;
 // This will be a graph that will have two groups of points, each one with its color, and the line that separates them. The line will be drawn with the least squares method.
 use raylib::prelude::*;
 
-type DataVector = Vec<(f32, f32, Color)>;
+use crate::{preprocessing::Scaler, projection::Projection};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Marker {
+    Circle,
+    Square,
+    Triangle,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GraphPoint {
+    pub x: f32,
+    pub y: f32,
+    pub color: Color,
+    pub marker: Marker,
+    // Ring drawn around the point, e.g. for corrupted samples
+    pub outline: Option<Color>,
+    // Index of the sample the point comes from
+    pub index: usize,
+}
+
+type DataVector = Vec<GraphPoint>;
 
 pub struct SeparationGraph {
     pub pos: Vector2,
@@ -11,11 +32,30 @@ pub struct SeparationGraph {
     pub height: f32,
     pub data: DataVector,
     pub line_color: Color,
+    // Bounds of the data
     pub max_point: Vector2,
     pub min_point: Vector2,
-    pub decision_line: Option<(f32, f32)>,
+    // Visible range, moved by zooming and panning
+    pub view_min: Vector2,
+    pub view_max: Vector2,
+    // The weights and bias of the line w·x + b = 0, in the units of the graph
+    pub decision_line: Option<([f64; 2], f64)>,
     pub x_axis: String,
     pub y_axis: String,
+    // Samples picked in another view, by sample index. The other points are faded.
+    pub selection: Option<Vec<bool>>,
+    // Sample pinned by a click, outlined with a ring
+    pub pinned: Option<usize>,
+    // Sample the neuron was last trained on, with a pulsing ring
+    pub current: Option<usize>,
+    // By sample index: the ones the neuron gets wrong are outlined, and the size of the points
+    // follows the loss when there are losses
+    pub misclassified: Vec<bool>,
+    pub losses: Option<Vec<f64>>,
+    // Probability at the nodes of a HEATMAP_CELLS grid over the view, row by row from the bottom
+    pub heatmap: Option<Vec<f64>>,
+    // Probabilities drawn as contour lines over the heatmap
+    pub contour_levels: Vec<f64>,
 }
 
 pub const Y_MARKS: usize = 4;
@@ -23,6 +63,99 @@ pub const X_MARKS: usize = Y_MARKS;
 pub const FONT_SIZE: i32 = 15;
 pub const DOT_RADIUS: f32 = 5.0;
 pub const PADDING: f32 = 0.3;
+// Smallest and largest view, relative to the one that fits the data
+const MIN_ZOOM: f32 = 1e-3;
+const MAX_ZOOM: f32 = 1e3;
+// Losses above it get the largest points
+const MAX_LOSS: f64 = 2.0;
+pub const HEATMAP_CELLS: usize = 40;
+
+// Blue for 0, white for 0.5 and red for 1
+pub fn probability_color(p: f64) -> Color {
+    let p = p.clamp(0.0, 1.0);
+    let fade = (255.0 * (1.0 - (2.0 * p - 1.0).abs())) as u8;
+
+    if p >= 0.5 {
+        Color::new(230, fade, fade, 255)
+    } else {
+        Color::new(fade, fade, 230, 255)
+    }
+}
+
+// A step of 1, 2 or 5 times a power of ten that gives about count steps between min and max.
+// Returns the multiples of the step inside the range and the decimals needed to show them.
+fn nice_ticks(min: f32, max: f32, count: usize) -> (Vec<f32>, usize) {
+    let range = (max - min) as f64;
+
+    if range <= 0.0 || !range.is_finite() {
+        return (vec![], 0);
+    }
+
+    let rough = range / count as f64;
+    let magnitude = 10f64.powf(rough.log10().floor());
+    let step = match rough / magnitude {
+        f if f < 1.5 => 1.0,
+        f if f < 3.0 => 2.0,
+        f if f < 7.0 => 5.0,
+        _ => 10.0,
+    } * magnitude;
+
+    let first = (min as f64 / step).ceil() as i64;
+    let last = (max as f64 / step).floor() as i64;
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+
+    // Computed from the index so the errors don't add up
+    let ticks = (first..=last).map(|i| (i as f64 * step) as f32).collect();
+
+    (ticks, decimals)
+}
+
+// The segment of the line w·x + b = 0 inside the rectangle between min and max, clipped like
+// Liang-Barsky does with the line written as p + t d
+fn clip_line(
+    weights: [f64; 2],
+    bias: f64,
+    min: [f64; 2],
+    max: [f64; 2],
+) -> Option<([f64; 2], [f64; 2])> {
+    let norm = weights[0] * weights[0] + weights[1] * weights[1];
+
+    // Without weights there's no line, every point has the same output
+    if norm < f64::EPSILON {
+        return None;
+    }
+
+    // The point of the line closest to the origin, and the direction along it
+    let point = [-bias * weights[0] / norm, -bias * weights[1] / norm];
+    let direction = [-weights[1], weights[0]];
+
+    let mut t_min = f64::NEG_INFINITY;
+    let mut t_max = f64::INFINITY;
+
+    for axis in 0..2 {
+        if direction[axis].abs() < f64::EPSILON {
+            // Parallel to the sides of this axis, it's either between them or outside
+            if point[axis] < min[axis] || point[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let t1 = (min[axis] - point[axis]) / direction[axis];
+        let t2 = (max[axis] - point[axis]) / direction[axis];
+
+        t_min = t_min.max(t1.min(t2));
+        t_max = t_max.min(t1.max(t2));
+    }
+
+    if t_min > t_max {
+        return None;
+    }
+
+    let at = |t: f64| [point[0] + t * direction[0], point[1] + t * direction[1]];
+
+    Some((at(t_min), at(t_max)))
+}
 
 impl SeparationGraph {
     pub fn new(
@@ -34,12 +167,12 @@ impl SeparationGraph {
         y_axis: String,
         data: DataVector,
     ) -> Self {
-        let max_x = data.iter().fold(f32::MIN, |a, &(x, _, _)| a.max(x));
-        let max_y = data.iter().fold(f32::MIN, |a, &(_, y, _)| a.max(y));
-        let min_x = data.iter().fold(f32::MAX, |a, &(x, _, _)| a.min(x));
-        let min_y = data.iter().fold(f32::MAX, |a, &(_, y, _)| a.min(y));
+        let max_x = data.iter().fold(f32::MIN, |a, p| a.max(p.x));
+        let max_y = data.iter().fold(f32::MIN, |a, p| a.max(p.y));
+        let min_x = data.iter().fold(f32::MAX, |a, p| a.min(p.x));
+        let min_y = data.iter().fold(f32::MAX, |a, p| a.min(p.y));
 
-        Self {
+        let mut graph = Self {
             pos: Vector2::new(x, y),
             width,
             height,
@@ -47,12 +180,82 @@ impl SeparationGraph {
             line_color: Color::BLUE,
             max_point: Vector2::new(max_x, max_y),
             min_point: Vector2::new(min_x, min_y),
+            view_min: Vector2::zero(),
+            view_max: Vector2::one(),
             x_axis,
             decision_line: None,
             y_axis,
+            selection: None,
+            pinned: None,
+            current: None,
+            misclassified: vec![],
+            losses: None,
+            heatmap: None,
+            contour_levels: vec![],
+        };
+
+        graph.fit();
+        graph
+    }
+
+    // Show all the data again
+    pub fn fit(&mut self) {
+        if self.data.is_empty() {
+            self.view_min = Vector2::zero();
+            self.view_max = Vector2::one();
+            return;
+        }
+
+        let max_x = self.data.iter().fold(f32::MIN, |a, p| a.max(p.x));
+        let max_y = self.data.iter().fold(f32::MIN, |a, p| a.max(p.y));
+        let min_x = self.data.iter().fold(f32::MAX, |a, p| a.min(p.x));
+        let min_y = self.data.iter().fold(f32::MAX, |a, p| a.min(p.y));
+
+        self.max_point = Vector2::new(max_x, max_y);
+        self.min_point = Vector2::new(min_x, min_y);
+
+        self.view_min = Vector2::new(min_x - PADDING, min_y - PADDING);
+        self.view_max = Vector2::new(max_x + PADDING, max_y + PADDING);
+    }
+
+    // Size of the view that shows all the data
+    fn fitted_span(&self) -> Vector2 {
+        let span = self.max_point - self.min_point + Vector2::new(2.0 * PADDING, 2.0 * PADDING);
+
+        if span.x.is_finite() && span.y.is_finite() && span.x > 0.0 && span.y > 0.0 {
+            span
+        } else {
+            Vector2::one()
         }
     }
 
+    // Zoom by the factor around a position on the screen, a factor below 1 zooms in. The view stays
+    // between MIN_ZOOM and MAX_ZOOM times the fitted one, so the scale never reaches 0 or infinity.
+    pub fn zoom(&mut self, center: Vector2, factor: f32) {
+        let center = self.get_value(center);
+        let span = self.view_max - self.view_min;
+        let fitted = self.fitted_span();
+
+        let min_factor = (MIN_ZOOM * fitted.x / span.x).max(MIN_ZOOM * fitted.y / span.y);
+        let max_factor = (MAX_ZOOM * fitted.x / span.x).min(MAX_ZOOM * fitted.y / span.y);
+        let factor = factor.max(min_factor).min(max_factor);
+
+        self.view_min = center + (self.view_min - center) * factor;
+        self.view_max = center + (self.view_max - center) * factor;
+    }
+
+    // Move the view with a mouse movement on the screen
+    pub fn pan(&mut self, delta: Vector2) {
+        let range = self.view_max - self.view_min;
+        let delta = Vector2::new(
+            delta.x / self.width * range.x,
+            -delta.y / self.height * range.y,
+        );
+
+        self.view_min -= delta;
+        self.view_max -= delta;
+    }
+
     // Draw the frame of the graph. It's just the left and bottom border.
     // Write the name of the axis on the end of each line
     // Create and arrow for the frame
@@ -95,46 +298,35 @@ impl SeparationGraph {
         );
     }
 
-    // This function should calculate the points of the decision line and the max and min points of the graph and should consider the decision line.
-    pub fn set_decision_line(&mut self, weights: &Vec<f64>, bias: f64) {
-        let x1 = self.min_point.x as f64;
-        let x2 = self.max_point.x as f64;
-
-        let y1 = (-weights[0] * x1 - bias) / weights[1];
-        let y2 = (-weights[0] * x2 - bias) / weights[1];
-
-        self.decision_line = Some((y1 as f32, y2 as f32));
-
-        let max_x = self.data.iter().fold(f32::MIN, |a, &(x, _, _)| a.max(x));
-        let max_y = self.data.iter().fold(f32::MIN, |a, &(_, y, _)| a.max(y));
-        let min_x = self.data.iter().fold(f32::MAX, |a, &(x, _, _)| a.min(x));
-        let min_y = self.data.iter().fold(f32::MAX, |a, &(_, y, _)| a.min(y));
-
-        let y1 = self.decision_line.unwrap().0;
-        let y2 = self.decision_line.unwrap().1;
-
-        let max_y = max_y.max(y1).max(y2);
-        let min_y = min_y.min(y1).min(y2);
-
-        self.max_point = Vector2::new(max_x, max_y);
-        self.min_point = Vector2::new(min_x, min_y);
+    // The weights are learned on scaled inputs, so the line is mapped back to the units of the data first.
+    // With a projection, the line is where the hyperplane of the neuron crosses the projected plane.
+    // Only the line is kept, it's clipped to the view when drawn.
+    pub fn set_decision_line(
+        &mut self,
+        weights: &[f64],
+        bias: f64,
+        scaler: &Scaler,
+        projection: Option<&Projection>,
+    ) {
+        let (weights, bias) = scaler.unscale_line(weights, bias);
+        let (weights, bias) = match projection {
+            Some(projection) => projection.project_line(&weights, bias),
+            None => (weights, bias),
+        };
+
+        self.decision_line = Some(([weights[0], weights[1]], bias));
     }
 
     // Draw the guide values with their numbers on the left side of the graph frame. The guide values should be proportional to the data values and separator line, always fixed values inside the limits of the graph.
     // The guide should be in the y and x axis.
     // Draw some light lines to guide the values.
     fn draw_guide_values(&self, d: &mut RaylibDrawHandle) {
-        let y_step = self.height / Y_MARKS as f32;
-        let x_step = self.width / X_MARKS as f32;
-
-        for i in 0..=Y_MARKS {
-            let y = self.pos.y + self.height - i as f32 * y_step;
-            let value = format!(
-                "{:.2}",
-                self.min_point.y - PADDING
-                    + i as f32 / Y_MARKS as f32
-                        * (self.max_point.y - self.min_point.y - 2.0 * -PADDING)
-            );
+        let (y_ticks, y_decimals) = nice_ticks(self.view_min.y, self.view_max.y, Y_MARKS);
+        let (x_ticks, x_decimals) = nice_ticks(self.view_min.x, self.view_max.x, X_MARKS);
+
+        for tick in y_ticks {
+            let y = self.get_y_proportional(tick);
+            let value = format!("{:.*}", y_decimals, tick);
 
             let size = d.measure_text(&value, FONT_SIZE);
 
@@ -154,14 +346,9 @@ impl SeparationGraph {
             );
         }
 
-        for i in 0..=X_MARKS {
-            let x = self.pos.x + i as f32 * x_step;
-            let value = format!(
-                "{:.2}",
-                self.min_point.x - PADDING
-                    + i as f32 / X_MARKS as f32
-                        * (self.max_point.x - self.min_point.x - 2.0 * -PADDING)
-            );
+        for tick in x_ticks {
+            let x = self.get_x_proportional(tick);
+            let value = format!("{:.*}", x_decimals, tick);
 
             let size = d.measure_text(&value, FONT_SIZE);
 
@@ -185,46 +372,370 @@ impl SeparationGraph {
     // Draw the boundary line that separates the two groups of points based on the weights and bias. The line should be proportional to the graph.
     // The tecnique is logical regression, the line is the result of the least squares method.
     fn draw_separation_line(&self, d: &mut RaylibDrawHandle) {
-        if let Some((y1, y2)) = self.decision_line {
-            let x1 = self.min_point.x;
-            let x2 = self.max_point.x;
+        let Some((weights, bias)) = self.decision_line else {
+            return;
+        };
+
+        let min = [self.view_min.x as f64, self.view_min.y as f64];
+        let max = [self.view_max.x as f64, self.view_max.y as f64];
+
+        // The line can miss the view entirely
+        if let Some((start, end)) = clip_line(weights, bias, min, max) {
+            let start = Vector2::new(
+                self.get_x_proportional(start[0] as f32),
+                self.get_y_proportional(start[1] as f32),
+            );
+            let end = Vector2::new(
+                self.get_x_proportional(end[0] as f32),
+                self.get_y_proportional(end[1] as f32),
+            );
 
-            let x1 = self.get_x_proportional(x1);
-            let x2 = self.get_x_proportional(x2);
-            let y1 = self.get_y_proportional(y1);
-            let y2 = self.get_y_proportional(y2);
+            d.draw_line_ex(start, end, 2.0, self.line_color);
+        }
+    }
 
-            d.draw_line_ex(
-                Vector2::new(x1, y1),
-                Vector2::new(x2, y2),
-                2.0,
-                self.line_color,
-            );
+    fn draw_marker(
+        d: &mut RaylibDrawHandle,
+        center: Vector2,
+        marker: Marker,
+        radius: f32,
+        color: Color,
+    ) {
+        match marker {
+            Marker::Circle => d.draw_circle_v(center, radius, color),
+            Marker::Square => d.draw_rectangle_v(
+                center - Vector2::new(radius, radius),
+                Vector2::new(2.0 * radius, 2.0 * radius),
+                color,
+            ),
+            // Counter-clockwise, as raylib expects
+            Marker::Triangle => d.draw_triangle(
+                center + Vector2::new(0.0, -radius * 1.2),
+                center + Vector2::new(-radius * 1.2, radius),
+                center + Vector2::new(radius * 1.2, radius),
+                color,
+            ),
+        }
+    }
+
+    // Half the usual size without loss, the usual size at a probability of 0.5, and twice it from
+    // MAX_LOSS on
+    fn point_radius(&self, index: usize) -> f32 {
+        match self.losses.as_ref().and_then(|losses| losses.get(index)) {
+            Some(loss) => DOT_RADIUS * (0.5 + 1.5 * (loss.min(MAX_LOSS) / MAX_LOSS) as f32),
+            None => DOT_RADIUS,
         }
     }
 
-    // Draw the points of the graph. Each point will have its color. Should be proportional to graph
+    // Draw the points of the graph. Each point will have its color and marker. Should be proportional to graph
     fn draw_points(&self, d: &mut RaylibDrawHandle) {
-        for (x, y, color) in &self.data {
-            let x = self.get_x_proportional(*x);
-            let y = self.get_y_proportional(*y);
+        for point in &self.data {
+            // Zoomed in, some points are out of the view
+            if !self.in_view(point) {
+                continue;
+            }
+
+            let x = self.get_x_proportional(point.x);
+            let y = self.get_y_proportional(point.y);
+
+            let selected = match &self.selection {
+                Some(selection) => selection.get(point.index).copied().unwrap_or(false),
+                None => true,
+            };
+            let fade = if selected { 1.0 } else { 0.15 };
+            let radius = self.point_radius(point.index);
+
+            if let Some(outline) = point.outline {
+                d.draw_circle_lines(x as i32, y as i32, radius + 3.0, outline.fade(fade));
+            }
+
+            // A slightly larger marker behind it makes the border
+            if self
+                .misclassified
+                .get(point.index)
+                .copied()
+                .unwrap_or(false)
+            {
+                Self::draw_marker(
+                    d,
+                    Vector2::new(x, y),
+                    point.marker,
+                    radius + 2.0,
+                    Color::BLACK.fade(fade),
+                );
+            }
+
+            Self::draw_marker(
+                d,
+                Vector2::new(x, y),
+                point.marker,
+                radius,
+                point.color.fade(fade),
+            );
+        }
 
-            d.draw_circle_v(Vector2::new(x, y), DOT_RADIUS, *color);
+        if let Some(center) = self.pinned.and_then(|index| self.screen_position(index)) {
+            d.draw_ring(
+                center,
+                DOT_RADIUS + 4.0,
+                DOT_RADIUS + 6.0,
+                0.0,
+                360.0,
+                24,
+                Color::BLACK,
+            );
+        }
+
+        if let Some(index) = self.current {
+            if let Some(center) = self.screen_position(index) {
+                let pulse = (d.get_time() * 6.0).sin() as f32;
+                let radius = self.point_radius(index) + 6.0 + 2.0 * pulse;
+
+                d.draw_ring(
+                    center,
+                    radius,
+                    radius + 2.0,
+                    0.0,
+                    360.0,
+                    24,
+                    Color::GOLD.fade(0.6 + 0.4 * pulse.abs()),
+                );
+            }
         }
     }
 
-    fn get_x_proportional(&self, x: f32) -> f32 {
-        let range_x = self.max_point.x - self.min_point.x - 2.0 * -PADDING;
-        let min_x = self.min_point.x - PADDING;
+    // Which marker is which subset, above the right side of the graph
+    fn draw_legend(&self, d: &mut RaylibDrawHandle) {
+        let mut x = self.pos.x + self.width;
+        let y = self.pos.y - FONT_SIZE as f32 - 5.0;
+
+        for (name, marker) in [
+            ("Test", Marker::Triangle),
+            ("Validation", Marker::Square),
+            ("Train", Marker::Circle),
+        ] {
+            x -= d.measure_text(name, FONT_SIZE) as f32;
+            d.draw_text(name, x as i32, y as i32, FONT_SIZE, Color::BLACK);
+
+            x -= DOT_RADIUS + 4.0;
+            Self::draw_marker(
+                d,
+                Vector2::new(x, y + FONT_SIZE as f32 / 2.0),
+                marker,
+                DOT_RADIUS,
+                Color::DARKGRAY,
+            );
 
-        self.pos.x + ((x - min_x) / range_x) * self.width
+            x -= DOT_RADIUS + 10.0;
+        }
+    }
+
+    fn get_x_proportional(&self, x: f32) -> f32 {
+        self.pos.x + ((x - self.view_min.x) / (self.view_max.x - self.view_min.x)) * self.width
     }
 
     fn get_y_proportional(&self, y: f32) -> f32 {
-        let range_y = self.max_point.y - self.min_point.y - 2.0 * -PADDING;
-        let min_y = self.min_point.y - PADDING;
+        self.pos.y + self.height
+            - ((y - self.view_min.y) / (self.view_max.y - self.view_min.y)) * self.height
+    }
+
+    // The inverse of get_x_proportional and get_y_proportional, from the screen to the data
+    pub fn get_value(&self, position: Vector2) -> Vector2 {
+        let range = self.view_max - self.view_min;
+
+        Vector2::new(
+            self.view_min.x + (position.x - self.pos.x) / self.width * range.x,
+            self.view_min.y + (self.pos.y + self.height - position.y) / self.height * range.y,
+        )
+    }
+
+    pub fn contains(&self, position: Vector2) -> bool {
+        Rectangle::new(self.pos.x, self.pos.y, self.width, self.height)
+            .check_collision_point_rec(position)
+    }
+
+    fn in_view(&self, point: &GraphPoint) -> bool {
+        point.x >= self.view_min.x
+            && point.x <= self.view_max.x
+            && point.y >= self.view_min.y
+            && point.y <= self.view_max.y
+    }
+
+    // Where the point of a sample is on the screen, if it's in the view
+    pub fn screen_position(&self, index: usize) -> Option<Vector2> {
+        self.data
+            .iter()
+            .find(|point| point.index == index && self.in_view(point))
+            .map(|point| {
+                Vector2::new(
+                    self.get_x_proportional(point.x),
+                    self.get_y_proportional(point.y),
+                )
+            })
+    }
+
+    // The point under the given screen position, the one drawn last when they overlap
+    pub fn point_at(&self, position: Vector2) -> Option<&GraphPoint> {
+        self.data
+            .iter()
+            .rev()
+            .filter(|point| self.in_view(point))
+            .find(|point| {
+                let center = Vector2::new(
+                    self.get_x_proportional(point.x),
+                    self.get_y_proportional(point.y),
+                );
+
+                center.distance_to(position) <= DOT_RADIUS + 2.0
+            })
+    }
+
+    // Replace the points without moving the view, so a point being dragged stays under the mouse
+    pub fn set_data(&mut self, data: DataVector) {
+        self.data = data;
+    }
 
-        self.pos.y + self.height - ((y - min_y) / range_y) * self.height
+    // The values at the nodes of the heatmap grid, row by row from the bottom of the view
+    pub fn grid_points(&self) -> Vec<Vector2> {
+        let min = self.view_min;
+        let size = self.view_max - self.view_min;
+
+        (0..=HEATMAP_CELLS)
+            .flat_map(|j| {
+                (0..=HEATMAP_CELLS).map(move |i| {
+                    Vector2::new(
+                        min.x + i as f32 / HEATMAP_CELLS as f32 * size.x,
+                        min.y + j as f32 / HEATMAP_CELLS as f32 * size.y,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    // Each cell is shaded by the mean probability of its corners
+    fn draw_heatmap(&self, d: &mut RaylibDrawHandle, heatmap: &[f64]) {
+        let nodes = HEATMAP_CELLS + 1;
+        let cell = Vector2::new(
+            self.width / HEATMAP_CELLS as f32,
+            self.height / HEATMAP_CELLS as f32,
+        );
+
+        for j in 0..HEATMAP_CELLS {
+            for i in 0..HEATMAP_CELLS {
+                let p = (heatmap[j * nodes + i]
+                    + heatmap[j * nodes + i + 1]
+                    + heatmap[(j + 1) * nodes + i]
+                    + heatmap[(j + 1) * nodes + i + 1])
+                    / 4.0;
+
+                d.draw_rectangle_v(
+                    Vector2::new(
+                        self.pos.x + i as f32 * cell.x,
+                        self.pos.y + self.height - (j + 1) as f32 * cell.y,
+                    ),
+                    // Overlap a little so no gaps show between the cells
+                    cell + Vector2::new(1.0, 1.0),
+                    probability_color(p).fade(0.35),
+                );
+            }
+        }
+    }
+
+    // Marching squares: each cell crossed by a level gets a segment between the crossed edges
+    fn draw_contours(&self, d: &mut RaylibDrawHandle, heatmap: &[f64]) {
+        let nodes = HEATMAP_CELLS + 1;
+        let cell = Vector2::new(
+            self.width / HEATMAP_CELLS as f32,
+            self.height / HEATMAP_CELLS as f32,
+        );
+        let node = |i: usize, j: usize| {
+            Vector2::new(
+                self.pos.x + i as f32 * cell.x,
+                self.pos.y + self.height - j as f32 * cell.y,
+            )
+        };
+
+        for &level in self.contour_levels.iter() {
+            for j in 0..HEATMAP_CELLS {
+                for i in 0..HEATMAP_CELLS {
+                    let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+
+                    let crossings: Vec<Vector2> = (0..4)
+                        .filter_map(|k| {
+                            let (a, b) = (corners[k], corners[(k + 1) % 4]);
+                            let (va, vb) = (heatmap[a.1 * nodes + a.0], heatmap[b.1 * nodes + b.0]);
+
+                            if (va - level) * (vb - level) >= 0.0 {
+                                return None;
+                            }
+
+                            let t = ((level - va) / (vb - va)) as f32;
+                            Some(node(a.0, a.1).lerp(node(b.0, b.1), t))
+                        })
+                        .collect();
+
+                    // Two crossings, or four at a saddle
+                    let mut crossings = crossings.into_iter();
+                    while let (Some(a), Some(b)) = (crossings.next(), crossings.next()) {
+                        d.draw_line_ex(a, b, 1.0, Color::DARKGRAY);
+                    }
+                }
+            }
+
+            // Label each level once, at the right border where it crosses
+            if let Some(j) = (0..HEATMAP_CELLS).find(|&j| {
+                let (a, b) = (
+                    heatmap[j * nodes + HEATMAP_CELLS],
+                    heatmap[(j + 1) * nodes + HEATMAP_CELLS],
+                );
+                (a - level) * (b - level) < 0.0
+            }) {
+                let position = node(HEATMAP_CELLS, j);
+
+                d.draw_text(
+                    &format!("{:.1}", level),
+                    position.x as i32 - 18,
+                    position.y as i32 - 12,
+                    10,
+                    Color::DARKGRAY,
+                );
+            }
+        }
+    }
+
+    // Probability scale on the right of the graph
+    fn draw_color_bar(&self, d: &mut RaylibDrawHandle) {
+        let x = self.pos.x + self.width + 8.0;
+        let steps = 50;
+        let step = self.height / steps as f32;
+
+        for k in 0..steps {
+            let p = 1.0 - (k as f64 + 0.5) / steps as f64;
+
+            d.draw_rectangle_v(
+                Vector2::new(x, self.pos.y + k as f32 * step),
+                Vector2::new(8.0, step + 1.0),
+                probability_color(p).fade(0.6),
+            );
+        }
+
+        d.draw_rectangle_lines(
+            x as i32,
+            self.pos.y as i32,
+            8,
+            self.height as i32,
+            Color::GRAY,
+        );
+
+        for (p, text) in [(1.0, "1"), (0.5, "0.5"), (0.0, "0")] {
+            d.draw_text(
+                text,
+                x as i32 + 11,
+                (self.pos.y + (1.0 - p) * self.height) as i32 - 5,
+                10,
+                Color::DARKGRAY,
+            );
+        }
     }
 
     pub fn remove_decision_line(&mut self) {
@@ -233,6 +744,12 @@ impl SeparationGraph {
 
     // Make a draw function that is proportional to the values of data, to always represent values inside the limits of the graph
     pub fn draw(&self, d: &mut RaylibDrawHandle) {
+        if let Some(heatmap) = &self.heatmap {
+            self.draw_heatmap(d, heatmap);
+            self.draw_contours(d, heatmap);
+            self.draw_color_bar(d);
+        }
+
         self.draw_frame(d);
 
         self.draw_guide_values(d);
@@ -240,5 +757,61 @@ impl SeparationGraph {
         self.draw_separation_line(d);
 
         self.draw_points(d);
+
+        if !self.data.is_empty() {
+            self.draw_legend(d);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: [f64; 2], b: [f64; 2]) {
+        assert!(
+            (a[0] - b[0]).abs() < 1e-9 && (a[1] - b[1]).abs() < 1e-9,
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn nice_ticks_use_round_steps() {
+        assert_eq!(nice_ticks(4.0, 8.2, 4), (vec![4.0, 5.0, 6.0, 7.0, 8.0], 0));
+
+        let (ticks, decimals) = nice_ticks(0.07, 0.93, 4);
+        assert_eq!(decimals, 1);
+        assert_eq!(ticks.len(), 4);
+        for (tick, expected) in ticks.iter().zip([0.2, 0.4, 0.6, 0.8]) {
+            assert!((tick - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn nice_ticks_of_empty_range() {
+        assert_eq!(nice_ticks(3.0, 3.0, 4), (vec![], 0));
+        assert_eq!(nice_ticks(3.0, 1.0, 4), (vec![], 0));
+    }
+
+    #[test]
+    fn clip_line_to_box() {
+        let (a, b) = clip_line([1.0, 1.0], -5.0, [0.0, 0.0], [4.0, 4.0]).unwrap();
+        assert_close(a, [4.0, 1.0]);
+        assert_close(b, [1.0, 4.0]);
+
+        // x = 2
+        let (a, b) = clip_line([1.0, 0.0], -2.0, [0.0, 0.0], [4.0, 4.0]).unwrap();
+        assert_close(a, [2.0, 0.0]);
+        assert_close(b, [2.0, 4.0]);
+    }
+
+    #[test]
+    fn clip_line_without_segment() {
+        // x + y = 20 passes beyond the corner
+        assert!(clip_line([1.0, 1.0], -20.0, [0.0, 0.0], [4.0, 4.0]).is_none());
+        assert!(clip_line([0.0, 1.0], -5.0, [0.0, 0.0], [4.0, 4.0]).is_none());
+        assert!(clip_line([0.0, 0.0], 1.0, [0.0, 0.0], [4.0, 4.0]).is_none());
     }
 }
@@ -0,0 +1,236 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::dataset::Sample;
+
+pub const DEFAULT_TRAIN_RATIO: f64 = 0.6;
+pub const DEFAULT_VALIDATION_RATIO: f64 = 0.2;
+pub const DEFAULT_SEED: u64 = 42;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Subset {
+    Train,
+    // Used to fit the calibration, never to train the neuron
+    Validation,
+    // Only used to measure the final model
+    Test,
+}
+
+impl Subset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Subset::Train => "train",
+            Subset::Validation => "validation",
+            Subset::Test => "test",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitConfig {
+    pub train_ratio: f64,
+    pub validation_ratio: f64,
+    // Keep the class proportions in every subset
+    pub stratified: bool,
+    pub seed: u64,
+}
+
+impl Default for SplitConfig {
+    fn default() -> Self {
+        Self {
+            train_ratio: DEFAULT_TRAIN_RATIO,
+            validation_ratio: DEFAULT_VALIDATION_RATIO,
+            stratified: true,
+            seed: DEFAULT_SEED,
+        }
+    }
+}
+
+impl SplitConfig {
+    pub fn test_ratio(&self) -> f64 {
+        1.0 - self.train_ratio - self.validation_ratio
+    }
+}
+
+// Disjoint train, validation and test sets, given as indexes of the samples in a shuffled order
+#[derive(Debug, Clone)]
+pub struct Split {
+    pub train: Vec<usize>,
+    pub validation: Vec<usize>,
+    pub test: Vec<usize>,
+    // The subset of every sample, in the order of the samples
+    pub subsets: Vec<Subset>,
+}
+
+impl Split {
+    // A sample added at the end of the data, trained on
+    pub fn push_train(&mut self, index: usize) {
+        self.train.push(index);
+        self.subsets.push(Subset::Train);
+    }
+
+    // Forget a removed sample. The samples after it move down by one.
+    pub fn remove(&mut self, index: usize) {
+        for indexes in [&mut self.train, &mut self.validation, &mut self.test] {
+            indexes.retain(|&i| i != index);
+
+            for i in indexes.iter_mut() {
+                if *i > index {
+                    *i -= 1;
+                }
+            }
+        }
+
+        self.subsets.remove(index);
+    }
+}
+
+pub fn split(data: &[Sample], config: &SplitConfig) -> Split {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+
+    let mut indexes: Vec<usize> = (0..data.len()).collect();
+    indexes.shuffle(&mut rng);
+
+    // Each class is split on its own when stratified, so every subset gets its share of it
+    let groups: Vec<Vec<usize>> = if config.stratified {
+        let mut classes: Vec<usize> = data.iter().map(|s| s.class).collect();
+        classes.sort();
+        classes.dedup();
+
+        classes
+            .iter()
+            .map(|&class| {
+                indexes
+                    .iter()
+                    .copied()
+                    .filter(|&i| data[i].class == class)
+                    .collect()
+            })
+            .collect()
+    } else {
+        vec![indexes]
+    };
+
+    let mut result = Split {
+        train: vec![],
+        validation: vec![],
+        test: vec![],
+        subsets: vec![Subset::Train; data.len()],
+    };
+
+    for group in groups {
+        // A ratio that rounds down to nothing still trains on one sample of every class
+        let train_len = ((group.len() as f64 * config.train_ratio).round() as usize)
+            .max(1)
+            .min(group.len());
+        let validation_len = ((group.len() as f64 * config.validation_ratio).round() as usize)
+            .min(group.len() - train_len);
+
+        result.train.extend(&group[..train_len]);
+        result
+            .validation
+            .extend(&group[train_len..train_len + validation_len]);
+        result.test.extend(&group[train_len + validation_len..]);
+    }
+
+    // Mix the classes again, the neuron is trained in this order
+    result.train.shuffle(&mut rng);
+
+    for &i in result.validation.iter() {
+        result.subsets[i] = Subset::Validation;
+    }
+
+    for &i in result.test.iter() {
+        result.subsets[i] = Subset::Test;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 50 samples of each of 3 classes
+    fn samples() -> Vec<Sample> {
+        (0..150)
+            .map(|i| Sample {
+                features: vec![i as f64],
+                class: i / 50,
+                row: Some(i),
+            })
+            .collect()
+    }
+
+    fn count(data: &[Sample], indexes: &[usize], class: usize) -> usize {
+        indexes.iter().filter(|&&i| data[i].class == class).count()
+    }
+
+    #[test]
+    fn stratified_split_keeps_class_counts() {
+        let data = samples();
+        let split = split(&data, &SplitConfig::default());
+
+        for class in 0..3 {
+            assert_eq!(count(&data, &split.train, class), 30);
+            assert_eq!(count(&data, &split.validation, class), 10);
+            assert_eq!(count(&data, &split.test, class), 10);
+        }
+
+        let mut all: Vec<usize> = [&split.train, &split.validation, &split.test]
+            .iter()
+            .flat_map(|indexes| indexes.iter().copied())
+            .collect();
+        all.sort();
+        assert_eq!(all, (0..150).collect::<Vec<usize>>());
+
+        for &i in split.validation.iter() {
+            assert_eq!(split.subsets[i], Subset::Validation);
+        }
+        for &i in split.test.iter() {
+            assert_eq!(split.subsets[i], Subset::Test);
+        }
+    }
+
+    #[test]
+    fn tiny_train_ratio_keeps_a_sample_of_every_class() {
+        let data = samples();
+
+        for stratified in [true, false] {
+            let config = SplitConfig {
+                train_ratio: 0.001,
+                stratified,
+                ..SplitConfig::default()
+            };
+            let split = split(&data, &config);
+
+            assert!(!split.train.is_empty());
+            if stratified {
+                for class in 0..3 {
+                    assert_eq!(count(&data, &split.train, class), 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_split() {
+        let data = samples();
+        let config = SplitConfig::default();
+
+        let first = split(&data, &config);
+        let second = split(&data, &config);
+
+        assert_eq!(first.train, second.train);
+        assert_eq!(first.validation, second.validation);
+        assert_eq!(first.test, second.test);
+
+        let other = split(
+            &data,
+            &SplitConfig {
+                seed: config.seed + 1,
+                ..config
+            },
+        );
+        assert_ne!(first.train, other.train);
+    }
+}
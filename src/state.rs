@@ -1,114 +1,625 @@
 use crate::{
-    neuron,
-    separation_graph::SeparationGraph,
-    train_data::{Iris, IrisData},
-    State,
+    calibration,
+    dataset::{Dataset, Sample},
+    formats::{self, PredictionRow},
+    imbalance,
+    model::Model,
+    neuron, perturbation,
+    preprocessing::{Scaler, SCALINGS},
+    projection::{self, Method, Projection, METHODS},
+    separation_graph::{GraphPoint, SeparationGraph},
+    split::{self, Subset},
+    statistics, State,
 };
-use rand::seq::SliceRandom;
-use raylib::prelude::*;
 
-pub fn update_data_type(state: &mut State, selected: i32) {
-    match selected {
-        0 => {
-            // Sepal
-            let graph_data = IrisData::get_graph_data(&state.data);
+// Index of "All" in the data type dropdown, "Custom" comes after it
+pub const ALL_FEATURES: i32 = 2;
+pub const CUSTOM_FEATURES: i32 = 3;
 
-            let sepal_data = graph_data
-                .iter()
-                .map(|(x, y, _, _, c)| (*x, *y, *c))
-                .collect::<Vec<(f32, f32, Color)>>();
-
-            state.separation_graph = SeparationGraph::new(
-                40.0,
-                380.0,
-                300.0,
-                300.0,
-                "SepalLengthCm".to_owned(),
-                "SepalWidthCm".to_owned(),
-                sepal_data,
-            );
-            state.neuron = neuron::Neuron::new(2, neuron::SIGMOID, 0.5);
-
-            state.train_data = state
-                .data
-                .iter()
-                .map(|d| (vec![d.sepal_length, d.sepal_width], d.species.into()))
-                .collect();
+type TrainData = Vec<(Vec<f64>, f64)>;
+
+// The training samples left by the downsampling, every sample after the perturbation with its
+// corruption, and the unscaled train data before any resampling
+fn training_rows(
+    state: &State,
+    features: &[usize],
+) -> (
+    Vec<usize>,
+    Vec<Sample>,
+    Vec<perturbation::Corruption>,
+    TrainData,
+) {
+    // One class can be made rare in the training split
+    let train = imbalance::downsample(&state.data, &state.split.train, &state.imbalance);
+
+    // The training samples are perturbed before the train data is built
+    let (perturbed, corruptions) = perturbation::perturb(
+        &state.data,
+        &train,
+        state.iris_type,
+        state.negative_class,
+        &state.perturbation,
+    );
+
+    let rows = Dataset::get_train_data(
+        &Dataset::select(&perturbed, &train),
+        features,
+        state.iris_type,
+    );
+
+    (train, perturbed, corruptions, rows)
+}
+
+// The data of the cross-validation panel. The copies made by the oversampling would land in both
+// the training and the held-out folds, so the folds are taken before resampling.
+pub fn cross_validation_data(state: &State) -> TrainData {
+    training_rows(state, &selected_features(state)).3
+}
+
+// The graph points and the unscaled train and validation data of the current samples
+fn build_data(state: &mut State, features: &[usize]) -> (Vec<GraphPoint>, TrainData, TrainData) {
+    let (train, perturbed, corruptions, rows) = training_rows(state, features);
+
+    let positions = imbalance::resample(&rows, &state.imbalance);
+
+    let train_data: TrainData = positions.iter().map(|&i| rows[i].clone()).collect();
+    state.train_samples = positions.iter().map(|&i| train[i]).collect();
+
+    let validation_data = Dataset::get_train_data(
+        &Dataset::select(&state.data, &state.split.validation),
+        features,
+        state.iris_type,
+    );
+
+    // More than two features are projected on a plane fitted on the train data
+    let fit_data = fitting_data(state, &train_data, features);
+    state.projection = match (features.len(), METHODS[state.projection_select as usize]) {
+        (2, _) => None,
+        (_, Method::Slice) => {
+            if state.slice_values.len() != features.len() {
+                state.slice_values =
+                    slice_means(&fit_data, features.len(), state.slice_means_select);
+            }
+            Some(slice_projection(state))
         }
-        1 => {
-            let graph_data = IrisData::get_graph_data(&state.data);
+        (_, method) => Projection::fit(method, &fit_data),
+    };
 
-            let petal = graph_data
-                .iter()
-                .map(|(_, _, x, y, c)| (*x, *y, *c))
-                .collect::<Vec<(f32, f32, Color)>>();
-
-            state.separation_graph = SeparationGraph::new(
-                40.0,
-                380.0,
-                300.0,
-                300.0,
-                "PetalLengthCm".to_owned(),
-                "PetalWidthCm".to_owned(),
-                petal,
-            );
-
-            state.neuron = neuron::Neuron::new(2, neuron::SIGMOID, 0.5);
-
-            state.train_data = state
-                .data
-                .iter()
-                .map(|d| (vec![d.petal_length, d.petal_width], d.species.into()))
-                .collect();
-        } // Petal
-        2 => {
-            state.separation_graph = SeparationGraph::new(
-                40.0,
-                380.0,
-                300.0,
-                300.0,
-                "".to_owned(),
-                "".to_owned(),
-                vec![],
-            );
-
-            state.neuron = neuron::Neuron::new(4, neuron::SIGMOID, 0.5);
-
-            state.train_data = state
-                .data
+    let graph_data = match (features, &state.projection) {
+        ([x, y], _) => {
+            Dataset::get_graph_data(&perturbed, &state.split.subsets, &corruptions, *x, *y)
+        }
+        (_, Some(projection)) => {
+            let projected: Vec<Sample> = perturbed
                 .iter()
-                .map(|d| {
-                    (
-                        vec![d.sepal_length, d.sepal_width, d.petal_length, d.petal_width],
-                        d.species.into(),
-                    )
+                .map(|sample| {
+                    let inputs: Vec<f64> = features.iter().map(|&i| sample.features[i]).collect();
+
+                    Sample {
+                        features: projection.project(&inputs).to_vec(),
+                        class: sample.class,
+                        row: sample.row,
+                    }
                 })
                 .collect();
-        } // All
-        _ => {}
+
+            Dataset::get_graph_data(&projected, &state.split.subsets, &corruptions, 0, 1)
+        }
+        _ => vec![],
+    }
+    .into_iter()
+    // The training samples dropped by the downsampling aren't shown
+    .filter(|point| {
+        state.split.subsets[point.index] != Subset::Train || train.contains(&point.index)
+    })
+    .collect();
+
+    state.corruptions = corruptions;
+
+    (graph_data, train_data, validation_data)
+}
+
+// The projections and slices are fitted on the train data, or on every sample of the pair when
+// all the training samples were deleted
+pub fn fitting_data(
+    state: &State,
+    train_data: &[(Vec<f64>, f64)],
+    features: &[usize],
+) -> TrainData {
+    if train_data.is_empty() {
+        Dataset::get_train_data(&state.data, features, state.iris_type)
+    } else {
+        train_data.to_vec()
+    }
+}
+
+// Indexes of the features of the neuron: all of them, or the X and Y features of the graph
+pub fn selected_features(state: &State) -> Vec<usize> {
+    if state.iris_select.1 == ALL_FEATURES {
+        (0..state.csv_data.feature_names.len()).collect()
+    } else {
+        vec![state.x_feature.1 as usize, state.y_feature.1 as usize]
+    }
+}
+
+// The data type of the X and Y features: a preset when the pair is one, else custom
+pub fn pair_data_type(state: &State) -> i32 {
+    let features = [state.x_feature.1 as usize, state.y_feature.1 as usize];
+
+    (0..ALL_FEATURES)
+        .find(|&preset| state.csv_data.selected_features(preset) == features)
+        .unwrap_or(CUSTOM_FEATURES)
+}
+
+// A new separation graph fitted to the data. A locked view is kept as long as the axes show the
+// same features.
+fn replace_graph(state: &mut State, x_axis: String, y_axis: String, data: Vec<GraphPoint>) {
+    let last = &state.separation_graph;
+    let view = (last.view_min, last.view_max);
+    let same_axes = last.x_axis == x_axis && last.y_axis == y_axis;
+
+    state.separation_graph = SeparationGraph::new(40.0, 380.0, 300.0, 300.0, x_axis, y_axis, data);
+
+    if state.lock_axes && same_axes {
+        (
+            state.separation_graph.view_min,
+            state.separation_graph.view_max,
+        ) = view;
+    }
+}
+
+pub fn update_data_type(state: &mut State, selected: i32) {
+    state.iris_select.1 = selected;
+    // A slice starts at the means again
+    state.slice_values.clear();
+
+    // Sepal and Petal are presets of the X and Y features
+    if selected < ALL_FEATURES {
+        let preset = state.csv_data.selected_features(selected);
+        state.x_feature.1 = preset[0] as i32;
+        state.y_feature.1 = preset[1] as i32;
     }
 
+    let features = selected_features(state);
+
+    let (graph_data, train_data, validation_data) = build_data(state, &features);
+
+    let (x_axis, y_axis) = match (&features[..], &state.projection) {
+        ([x, y], _) => (
+            state.csv_data.feature_names[*x].clone(),
+            state.csv_data.feature_names[*y].clone(),
+        ),
+        (_, Some(projection)) => projection.axis_names(),
+        _ => ("".to_owned(), "".to_owned()),
+    };
+
+    replace_graph(state, x_axis, y_axis, graph_data);
+
+    state.neuron = neuron::Neuron::new(features.len(), neuron::SIGMOID, 0.5);
+
+    // Fitted on the training split only, then applied to the validation samples
+    state.scaler = Scaler::fit(
+        SCALINGS[state.scaling_select.1 as usize],
+        &train_data,
+        features.len(),
+    );
+    state.train_data = state.scaler.transform_data(&train_data);
+    state.validation_data = state.scaler.transform_data(&validation_data);
+
+    state.neuron.class_weights = imbalance::class_weights(&state.train_data, &state.imbalance);
+
     state.line_graph.clear_data();
+    state.cross_validation = None;
+    state.calibrator = calibration::Calibrator::None;
+    state.calibrator_select = 0;
     state.pause = true;
     state.generations = 0;
     state.data_index = 0;
+
+    update_point_markers(state);
 }
 
-pub fn update_iris_type(state: &mut State, iris_type: Iris) {
-    match iris_type {
-        Iris::Versicolour => {
-            state.data = IrisData::get_data(&state.csv_data, Iris::Versicolour);
-        }
-        Iris::Virginica => {
-            state.data = IrisData::get_data(&state.csv_data, Iris::Virginica);
+// Values of the fixed features of a slice: the mean of the positive class (0), of the negative
+// class (1), or halfway between them (2). Zero without any data.
+pub fn slice_means(train_data: &[(Vec<f64>, f64)], inputs: usize, select: i32) -> Vec<f64> {
+    let positive = projection::target_mean(train_data, true);
+    let negative = projection::target_mean(train_data, false);
+
+    match (select, positive, negative) {
+        (0, Some(positive), _) => positive,
+        (1, _, Some(negative)) => negative,
+        (_, Some(positive), Some(negative)) => positive
+            .iter()
+            .zip(negative)
+            .map(|(p, n)| (p + n) / 2.0)
+            .collect(),
+        (_, positive, negative) => positive.or(negative).unwrap_or(vec![0.0; inputs]),
+    }
+}
+
+// The slice through the fixed values along the X and Y features
+fn slice_projection(state: &State) -> Projection {
+    let (x, y) = (state.x_feature.1 as usize, state.y_feature.1 as usize);
+
+    Projection::slice(
+        x,
+        y,
+        &state.slice_values,
+        [
+            state.csv_data.feature_names[x].clone(),
+            state.csv_data.feature_names[y].clone(),
+        ],
+    )
+}
+
+// Move the slice to the fixed values, the points stay where they are
+pub fn update_slice(state: &mut State) {
+    state.projection = Some(slice_projection(state));
+
+    update_decision_line(state);
+}
+
+// Switch between the projections of the "All" view, without resetting the neuron
+pub fn update_projection(state: &mut State) {
+    let features = selected_features(state);
+
+    let (graph_data, _, _) = build_data(state, &features);
+
+    let (x_axis, y_axis) = match &state.projection {
+        Some(projection) => projection.axis_names(),
+        None => return,
+    };
+
+    replace_graph(state, x_axis, y_axis, graph_data);
+
+    update_decision_line(state);
+}
+
+// Rebuild the data after the samples were edited. The neuron keeps training, and the scaler is
+// kept so its weights still mean the same.
+fn refresh_data(state: &mut State) {
+    let features = selected_features(state);
+
+    let (graph_data, train_data, validation_data) = build_data(state, &features);
+
+    state.separation_graph.set_data(graph_data);
+
+    state.train_data = state.scaler.transform_data(&train_data);
+    state.validation_data = state.scaler.transform_data(&validation_data);
+    state.test_data = Dataset::get_test_data(&Dataset::select(&state.data, &state.split.test));
+
+    state.neuron.class_weights = imbalance::class_weights(&state.train_data, &state.imbalance);
+
+    if state.data_index >= state.train_data.len() {
+        state.data_index = 0;
+    }
+
+    state.cross_validation = None;
+
+    update_point_markers(state);
+}
+
+// The class of a point added with the right mouse button
+fn negative_of_pair(state: &State) -> Option<usize> {
+    state
+        .negative_class
+        .or_else(|| (0..state.csv_data.class_names.len()).find(|&class| class != state.iris_type))
+}
+
+// Add a training sample of the positive class, or of the negative one, at the given values of the
+// two features in the graph. Its other features are the means of its class.
+pub fn add_sample(state: &mut State, x: f64, y: f64, positive: bool) {
+    let class = if positive {
+        state.iris_type
+    } else {
+        match negative_of_pair(state) {
+            Some(class) => class,
+            None => return,
         }
-        _ => {}
+    };
+
+    let features = selected_features(state);
+    let [x_feature, y_feature] = features[..] else {
+        return;
+    };
+
+    let mut values: Vec<f64> = (0..state.csv_data.feature_names.len())
+        .map(|feature| {
+            statistics::class_summary(&state.data, class, feature)
+                .map(|summary| summary.mean)
+                .unwrap_or(0.0)
+        })
+        .collect();
+    values[x_feature] = x;
+    values[y_feature] = y;
+
+    state.data.push(Sample {
+        features: values,
+        class,
+        row: None,
+    });
+    state.split.push_train(state.data.len() - 1);
+
+    refresh_data(state);
+}
+
+// Move a sample to the given values of the two features in the graph
+pub fn move_sample(state: &mut State, index: usize, x: f64, y: f64) {
+    let features = selected_features(state);
+    let [x_feature, y_feature] = features[..] else {
+        return;
+    };
+
+    state.data[index].features[x_feature] = x;
+    state.data[index].features[y_feature] = y;
+
+    refresh_data(state);
+}
+
+pub fn remove_sample(state: &mut State, index: usize) {
+    state.data.remove(index);
+    state.split.remove(index);
+
+    // The samples after it move down by one
+    state.selected_sample = match state.selected_sample {
+        Some(selected) if selected == index => None,
+        Some(selected) if selected > index => Some(selected - 1),
+        selected => selected,
+    };
+
+    refresh_data(state);
+}
+
+// Replace the dataset, pairing its last class with the first one again
+pub fn load_dataset(state: &mut State, dataset: Dataset) {
+    state.csv_data = dataset;
+
+    state.iris_type = state.csv_data.class_names.len() - 1;
+    state.iris_type_component = (false, state.iris_type as i32);
+    state.negative_class = Some(0);
+    state.negative_component = (false, 0);
+    state.iris_select = (false, 0);
+    state.brushes = vec![None; state.csv_data.feature_names.len()];
+
+    update_class_pair(state);
+    update_data_type(state, 0);
+}
+
+pub fn update_class_pair(state: &mut State) {
+    state.data = state
+        .csv_data
+        .get_data(state.iris_type, state.negative_class);
+    state.split = split::split(&state.data, &state.split_config);
+    state.test_data = Dataset::get_test_data(&Dataset::select(&state.data, &state.split.test));
+    state.selected_sample = None;
+}
+
+// The current training data in the units of the dataset
+pub fn unscaled_train_data(state: &State) -> Vec<(Vec<f64>, f64)> {
+    state
+        .train_data
+        .iter()
+        .map(|(inputs, target)| (state.scaler.inverse_transform(inputs), *target))
+        .collect()
+}
+
+// The samples inside every brushed range of the parallel coordinates, None without a brush
+pub fn brushed_samples(state: &State) -> Option<Vec<bool>> {
+    if state.brushes.iter().all(|brush| brush.is_none()) {
+        return None;
+    }
+
+    Some(
+        state
+            .data
+            .iter()
+            .map(|sample| {
+                state
+                    .brushes
+                    .iter()
+                    .zip(sample.features.iter())
+                    .all(|(brush, value)| match brush {
+                        Some((low, high)) => low <= value && value <= high,
+                        None => true,
+                    })
+            })
+            .collect(),
+    )
+}
+
+// Calibrated output of the neuron for a sample in the units of the data
+pub fn predictor(state: &State) -> impl Fn(&Sample) -> f64 + '_ {
+    let model = current_model(state);
+    let features = selected_features(state);
+
+    move |sample| {
+        let inputs: Vec<f64> = features.iter().map(|&i| sample.features[i]).collect();
+        state.calibrator.apply(model.predict(&inputs))
+    }
+}
+
+// The probability of a sample and its loss
+pub fn sample_prediction(state: &State, index: usize) -> (f64, Option<f64>) {
+    let sample = &state.data[index];
+    let probability = predictor(state)(sample);
+
+    (
+        probability,
+        formats::sample_loss(sample, probability, state.iris_type, state.negative_class),
+    )
+}
+
+// Every sample with the output of the neuron, or only the samples of one subset
+pub fn prediction_rows(state: &State, only: Option<Subset>) -> Vec<PredictionRow> {
+    formats::prediction_rows(
+        &state.csv_data,
+        &state.data,
+        &state.split.subsets,
+        state.iris_type,
+        state.negative_class,
+        only,
+        predictor(state),
+    )
+}
+
+pub fn current_model(state: &State) -> Model {
+    let features = selected_features(state);
+
+    Model {
+        feature_names: features
+            .iter()
+            .map(|&i| state.csv_data.feature_names[i].clone())
+            .collect(),
+        scaler: state.scaler.clone(),
+        weights: state.neuron.weights.clone(),
+        bias: state.neuron.biase,
+        positive: state.csv_data.class_names[state.iris_type].clone(),
+        negative: state
+            .negative_class
+            .map(|negative| state.csv_data.class_names[negative].clone()),
+        split: state.split_config.clone(),
+    }
+}
+
+// Use a saved model in place of the current neuron. Its features, classes and split have to match
+// the selected ones.
+pub fn apply_model(state: &mut State, model: Model) -> Result<(), String> {
+    let current = current_model(state);
+
+    if model.feature_names != current.feature_names {
+        return Err(format!(
+            "The model uses {}, not {}",
+            model.feature_names.join(", "),
+            current.feature_names.join(", ")
+        ));
+    }
+
+    if (&model.positive, &model.negative) != (&current.positive, &current.negative) {
+        return Err(format!(
+            "The model is {}, not {}",
+            model.pair_name(),
+            current.pair_name()
+        ));
+    }
+
+    // On another split, the validation samples could be ones the model was trained on
+    if model.split != current.split {
+        return Err(format!(
+            "The model was trained on another split (seed {})",
+            model.split.seed
+        ));
     }
-    state.data.shuffle(&mut rand::thread_rng());
-    state.test_data = IrisData::get_test_data(&state.data, 3);
+
+    // Bring the data to the scaling of the model
+    let train_data = unscaled_train_data(state);
+    let validation_data: Vec<(Vec<f64>, f64)> = state
+        .validation_data
+        .iter()
+        .map(|(inputs, target)| (state.scaler.inverse_transform(inputs), *target))
+        .collect();
+
+    state.train_data = model.scaler.transform_data(&train_data);
+    state.validation_data = model.scaler.transform_data(&validation_data);
+
+    state.scaling_select.1 = SCALINGS
+        .iter()
+        .position(|&s| s == model.scaler.scaling)
+        .unwrap_or(0) as i32;
+    state.scaler = model.scaler;
+    state.neuron.weights = model.weights;
+    state.neuron.biase = model.bias;
+
+    update_decision_line(state);
+
+    state.calibrator = calibration::Calibrator::None;
+    state.calibrator_select = 0;
+
+    Ok(())
+}
+
+// The line of the neuron on the separation graph, projected when it has more than two inputs
+pub fn update_decision_line(state: &mut State) {
+    if state.neuron.inputs == 2 || state.projection.is_some() {
+        state.separation_graph.set_decision_line(
+            &state.neuron.weights,
+            state.neuron.biase,
+            &state.scaler,
+            state.projection.as_ref(),
+        )
+    }
+
+    update_heatmap(state);
+    update_point_markers(state);
+}
+
+// Which samples the neuron gets wrong against their true class, and the loss of each one
+fn update_point_markers(state: &mut State) {
+    let (misclassified, losses): (Vec<bool>, Vec<f64>) = {
+        let predict = predictor(state);
+
+        state
+            .data
+            .iter()
+            .map(|sample| {
+                let probability = predict(sample);
+                let loss = formats::sample_loss(
+                    sample,
+                    probability,
+                    state.iris_type,
+                    state.negative_class,
+                );
+                let wrong = (probability >= 0.5) != (sample.class == state.iris_type);
+
+                (loss.is_some() && wrong, loss.unwrap_or(0.0))
+            })
+            .unzip()
+    };
+
+    state.separation_graph.misclassified = misclassified;
+    state.separation_graph.losses = state.loss_size.then_some(losses);
+}
+
+// The calibrated probability over the view of the separation graph. Every point of the graph is
+// turned back into inputs of the neuron, so it works for projections too.
+fn update_heatmap(state: &mut State) {
+    let can_show = state.neuron.inputs == 2 || state.projection.is_some();
+
+    if !state.show_heatmap || !can_show {
+        state.separation_graph.heatmap = None;
+        return;
+    }
+
+    let model = current_model(state);
+
+    let heatmap = state
+        .separation_graph
+        .grid_points()
+        .iter()
+        .map(|point| {
+            let point = [point.x as f64, point.y as f64];
+            let inputs = match &state.projection {
+                Some(projection) => projection.unproject(point),
+                None => point.to_vec(),
+            };
+
+            state.calibrator.apply(model.predict(&inputs))
+        })
+        .collect();
+
+    state.separation_graph.heatmap = Some(heatmap);
+    state.separation_graph.contour_levels = if state.show_contours {
+        vec![0.1, 0.5, 0.9]
+    } else {
+        vec![]
+    };
 }
 
 pub fn update_data(state: &mut State) {
+    // Every training sample can be deleted from the graph
+    if state.train_data.is_empty() {
+        return;
+    }
+
     let output = state
         .neuron
         .feed_forward(&state.train_data[state.data_index].0);
@@ -119,12 +630,9 @@ pub fn update_data(state: &mut State) {
     state.outputs = (output, error, gradient);
 
     state.target = Some(state.train_data[state.data_index].1);
+    state.separation_graph.current = state.train_samples.get(state.data_index).copied();
 
-    if state.neuron.inputs == 2 {
-        state
-            .separation_graph
-            .set_decision_line(&state.neuron.weights, state.neuron.biase)
-    }
+    update_decision_line(state);
 
     state.loss = state
         .neuron
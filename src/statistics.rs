@@ -0,0 +1,127 @@
+use crate::{dataset::Sample, preprocessing::quantile};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: f64,
+    pub std: f64,
+    pub min: f64,
+    pub q1: f64,
+    pub median: f64,
+    pub q3: f64,
+    pub max: f64,
+}
+
+impl Summary {
+    pub fn from_values(values: &[f64]) -> Option<Summary> {
+        if values.is_empty() {
+            return None;
+        }
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        let mean = sorted.iter().sum::<f64>() / sorted.len() as f64;
+        let variance = sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / sorted.len() as f64;
+
+        Some(Summary {
+            count: sorted.len(),
+            mean,
+            std: variance.sqrt(),
+            min: sorted[0],
+            q1: quantile(&sorted, 0.25),
+            median: quantile(&sorted, 0.5),
+            q3: quantile(&sorted, 0.75),
+            max: sorted[sorted.len() - 1],
+        })
+    }
+}
+
+fn feature_values(data: &[Sample], feature: usize) -> Vec<f64> {
+    data.iter().map(|s| s.features[feature]).collect()
+}
+
+pub fn class_summary(data: &[Sample], class: usize, feature: usize) -> Option<Summary> {
+    let values: Vec<f64> = data
+        .iter()
+        .filter(|s| s.class == class)
+        .map(|s| s.features[feature])
+        .collect();
+
+    Summary::from_values(&values)
+}
+
+// Counts of the values in equal bins between min and max
+pub fn histogram(values: &[f64], min: f64, max: f64, bins: usize) -> Vec<usize> {
+    let mut counts = vec![0; bins];
+    let width = (max - min).max(f64::EPSILON) / bins as f64;
+
+    for value in values {
+        let bin = ((value - min) / width).floor().max(0.0) as usize;
+        counts[bin.min(bins - 1)] += 1;
+    }
+
+    counts
+}
+
+pub fn pearson(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len() as f64;
+    let mean_a = a.iter().sum::<f64>() / n;
+    let mean_b = b.iter().sum::<f64>() / n;
+
+    let mut covariance = 0.0;
+    let mut variance_a = 0.0;
+    let mut variance_b = 0.0;
+
+    for (x, y) in a.iter().zip(b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        variance_a += (x - mean_a).powi(2);
+        variance_b += (y - mean_b).powi(2);
+    }
+
+    let denominator = (variance_a * variance_b).sqrt();
+
+    // A constant feature isn't correlated with anything
+    if denominator > f64::EPSILON {
+        covariance / denominator
+    } else {
+        0.0
+    }
+}
+
+// Pearson correlation of every pair of features
+pub fn correlation_matrix(data: &[Sample], features: usize) -> Vec<Vec<f64>> {
+    let values: Vec<Vec<f64>> = (0..features).map(|i| feature_values(data, i)).collect();
+
+    values
+        .iter()
+        .map(|a| values.iter().map(|b| pearson(a, b)).collect())
+        .collect()
+}
+
+// Fisher's ratio (m1 - m0)² / (s1² + s0²) of the positive class against the others.
+// The higher it is, the better the feature alone separates them.
+pub fn fisher_score(data: &[Sample], feature: usize, positive: usize) -> f64 {
+    let (positives, negatives): (Vec<&Sample>, Vec<&Sample>) =
+        data.iter().partition(|s| s.class == positive);
+
+    let positive = Summary::from_values(
+        &positives
+            .iter()
+            .map(|s| s.features[feature])
+            .collect::<Vec<f64>>(),
+    );
+    let negative = Summary::from_values(
+        &negatives
+            .iter()
+            .map(|s| s.features[feature])
+            .collect::<Vec<f64>>(),
+    );
+
+    match (positive, negative) {
+        (Some(p), Some(n)) => {
+            (p.mean - n.mean).powi(2) / (p.std.powi(2) + n.std.powi(2)).max(f64::EPSILON)
+        }
+        _ => 0.0,
+    }
+}
@@ -0,0 +1,154 @@
+// Generated 2D datasets to show where a single neuron (a straight decision line) fails
+use std::f64::consts::PI;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::dataset::{Dataset, Sample};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Generator {
+    Blobs,
+    Xor,
+    Moons,
+    Circles,
+    Spirals,
+    Linear,
+}
+
+pub const GENERATORS: [Generator; 6] = [
+    Generator::Blobs,
+    Generator::Xor,
+    Generator::Moons,
+    Generator::Circles,
+    Generator::Spirals,
+    Generator::Linear,
+];
+
+impl Generator {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Generator::Blobs => "Blobs",
+            Generator::Xor => "XOR",
+            Generator::Moons => "Moons",
+            Generator::Circles => "Circles",
+            Generator::Spirals => "Spirals",
+            Generator::Linear => "Linear",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntheticConfig {
+    pub generator: Generator,
+    pub samples: usize,
+    // Standard deviation of the Gaussian noise added to every point
+    pub noise: f64,
+    pub seed: u64,
+    // Empty band around the line, only used by the linear generator
+    pub margin: f64,
+}
+
+impl Default for SyntheticConfig {
+    fn default() -> Self {
+        Self {
+            generator: Generator::Blobs,
+            samples: 200,
+            noise: 0.1,
+            seed: 42,
+            margin: 0.2,
+        }
+    }
+}
+
+// Box-Muller transform, so no extra distribution crate is needed
+pub fn gaussian(rng: &mut StdRng) -> f64 {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen_range(0.0..1.0);
+
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+// The point and the class (0 or 1) of the i-th sample
+fn point(config: &SyntheticConfig, i: usize, rng: &mut StdRng) -> (f64, f64, usize) {
+    // Half of the samples go to each class, except when the class depends on the position
+    let class = i % 2;
+
+    match config.generator {
+        Generator::Blobs => {
+            let center = if class == 0 { -1.0 } else { 1.0 };
+            (
+                center + 0.4 * gaussian(rng),
+                center + 0.4 * gaussian(rng),
+                class,
+            )
+        }
+        Generator::Xor => {
+            let x: f64 = rng.gen_range(-1.0..1.0);
+            let y: f64 = rng.gen_range(-1.0..1.0);
+            (x, y, (x * y > 0.0) as usize)
+        }
+        Generator::Moons => {
+            let t = rng.gen_range(0.0..PI);
+
+            if class == 0 {
+                (t.cos(), t.sin(), class)
+            } else {
+                (1.0 - t.cos(), 0.5 - t.sin(), class)
+            }
+        }
+        Generator::Circles => {
+            let t = rng.gen_range(0.0..2.0 * PI);
+            let radius = if class == 0 { 1.0 } else { 0.5 };
+            (radius * t.cos(), radius * t.sin(), class)
+        }
+        Generator::Spirals => {
+            // Two arms turning one and a half times, the second one rotated by half a turn
+            let t: f64 = rng.gen_range(0.1..1.0);
+            let angle = t * 3.0 * PI + class as f64 * PI;
+            (t * angle.cos(), t * angle.sin(), class)
+        }
+        Generator::Linear => loop {
+            let x: f64 = rng.gen_range(-1.0..1.0);
+            let y: f64 = rng.gen_range(-1.0..1.0);
+
+            // Signed distance to the line y = x
+            let distance = (y - x) / 2.0_f64.sqrt();
+
+            if distance.abs() >= config.margin / 2.0 {
+                break (x, y, (distance > 0.0) as usize);
+            }
+        },
+    }
+}
+
+pub fn generate(config: &SyntheticConfig) -> Dataset {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+
+    let samples = (0..config.samples)
+        .map(|i| {
+            let (x, y, class) = point(config, i, &mut rng);
+
+            Sample {
+                features: vec![
+                    x + config.noise * gaussian(&mut rng),
+                    y + config.noise * gaussian(&mut rng),
+                ],
+                class,
+                row: Some(i),
+            }
+        })
+        .collect();
+
+    Dataset {
+        feature_names: vec!["x1".to_owned(), "x2".to_owned()],
+        class_names: vec!["Class A".to_owned(), "Class B".to_owned()],
+        samples,
+        skipped: vec![],
+        source: format!(
+            "{} ({} samples, seed {})",
+            config.generator.name(),
+            config.samples,
+            config.seed
+        ),
+    }
+}